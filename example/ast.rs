@@ -17,6 +17,7 @@ pub struct IfStmt {
     /// then statement
     pub then: Stmt,
     /// else statement, can be empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub otherwise: Option<Stmt>,
 }
 
@@ -49,6 +50,8 @@ pub enum Expr {
     Identifier(Identifier),
     /// binary expression
     BinaryExpr(Box<BinaryExpr>),
+    /// function call expression
+    CallExpr(Box<CallExpr>),
 }
 
 #[derive(Archive, Clone, Copy, Debug, Deserialize, Serialize)]
@@ -80,3 +83,9 @@ pub struct BinaryExpr {
     /// right operand
     pub right: Expr,
 }
+
+#[derive(Archive, Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct CallExpr {
+    pub r#fn: Expr,
+    pub args: Vec<Expr>,
+}
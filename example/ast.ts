@@ -42,6 +42,7 @@ export type Expr =
     | BoolLiteral
     | Identifier
     | BinaryExpr
+    | CallExpr
 
 export interface ExprBase<K extends string> {
     $kind: K;
@@ -76,3 +77,9 @@ export interface BinaryExpr extends ExprBase<"BinaryExpr"> {
     /** right operand */
     right: Expr,
 }
+
+/** function call expression */
+export interface CallExpr extends ExprBase<"CallExpr"> {
+    fn: Expr,
+    args: Expr[],
+}
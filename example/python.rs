@@ -0,0 +1,888 @@
+#[derive(Clone, Debug)]
+pub enum r#mod {
+    Module(Module),
+    Interactive(Interactive),
+    Expression(Expression),
+    FunctionType(FunctionType),
+}
+
+#[derive(Clone, Debug)]
+pub struct Module {
+    pub body: Vec<stmt>,
+    pub type_ignores: Vec<type_ignore>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Interactive {
+    pub body: Vec<stmt>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Expression {
+    pub body: expr,
+}
+
+#[derive(Clone, Debug)]
+pub struct FunctionType {
+    pub argtypes: Vec<expr>,
+    pub returns: expr,
+}
+
+#[derive(Clone, Debug)]
+pub enum stmt {
+    FunctionDef(FunctionDef),
+    AsyncFunctionDef(AsyncFunctionDef),
+    ClassDef(ClassDef),
+    Return(Return),
+    Delete(Delete),
+    Assign(Assign),
+    AugAssign(AugAssign),
+    AnnAssign(AnnAssign),
+    For(For),
+    AsyncFor(AsyncFor),
+    While(While),
+    If(If),
+    With(With),
+    AsyncWith(AsyncWith),
+    Match(Match),
+    Raise(Raise),
+    Try(Try),
+    TryStar(TryStar),
+    Assert(Assert),
+    Import(Import),
+    ImportFrom(ImportFrom),
+    Global(Global),
+    Nonlocal(Nonlocal),
+    Expr(Expr),
+    Pass(Pass),
+    Break(Break),
+    Continue(Continue),
+}
+
+#[derive(Clone, Debug)]
+pub struct Pass {
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Break {
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Continue {
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct FunctionDef {
+    pub name: String,
+    pub args: arguments,
+    pub body: Vec<stmt>,
+    pub decorator_list: Vec<expr>,
+    pub returns: Option<expr>,
+    pub type_comment: Option<String>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AsyncFunctionDef {
+    pub name: String,
+    pub args: arguments,
+    pub body: Vec<stmt>,
+    pub decorator_list: Vec<expr>,
+    pub returns: Option<expr>,
+    pub type_comment: Option<String>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ClassDef {
+    pub name: String,
+    pub bases: Vec<expr>,
+    pub keywords: Vec<keyword>,
+    pub body: Vec<stmt>,
+    pub decorator_list: Vec<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Return {
+    pub value: Option<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Delete {
+    pub targets: Vec<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Assign {
+    pub targets: Vec<expr>,
+    pub value: expr,
+    pub type_comment: Option<String>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AugAssign {
+    pub target: expr,
+    pub op: operator,
+    pub value: expr,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AnnAssign {
+    pub target: expr,
+    pub annotation: expr,
+    pub value: Option<expr>,
+    pub simple: i64,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct For {
+    pub target: expr,
+    pub iter: expr,
+    pub body: Vec<stmt>,
+    pub orelse: Vec<stmt>,
+    pub type_comment: Option<String>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AsyncFor {
+    pub target: expr,
+    pub iter: expr,
+    pub body: Vec<stmt>,
+    pub orelse: Vec<stmt>,
+    pub type_comment: Option<String>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct While {
+    pub test: expr,
+    pub body: Vec<stmt>,
+    pub orelse: Vec<stmt>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct If {
+    pub test: expr,
+    pub body: Vec<stmt>,
+    pub orelse: Vec<stmt>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct With {
+    pub items: Vec<withitem>,
+    pub body: Vec<stmt>,
+    pub type_comment: Option<String>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AsyncWith {
+    pub items: Vec<withitem>,
+    pub body: Vec<stmt>,
+    pub type_comment: Option<String>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Match {
+    pub subject: expr,
+    pub cases: Vec<match_case>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Raise {
+    pub exc: Option<expr>,
+    pub cause: Option<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Try {
+    pub body: Vec<stmt>,
+    pub handlers: Vec<excepthandler>,
+    pub orelse: Vec<stmt>,
+    pub finalbody: Vec<stmt>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TryStar {
+    pub body: Vec<stmt>,
+    pub handlers: Vec<excepthandler>,
+    pub orelse: Vec<stmt>,
+    pub finalbody: Vec<stmt>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Assert {
+    pub test: expr,
+    pub msg: Option<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Import {
+    pub names: Vec<alias>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ImportFrom {
+    pub module: Option<String>,
+    pub names: Vec<alias>,
+    pub level: Option<i64>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Global {
+    pub names: Vec<String>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Nonlocal {
+    pub names: Vec<String>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Expr {
+    pub value: expr,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub enum expr {
+    BoolOp(BoolOp),
+    NamedExpr(NamedExpr),
+    BinOp(BinOp),
+    UnaryOp(UnaryOp),
+    Lambda(Lambda),
+    IfExp(IfExp),
+    Dict(Dict),
+    Set(Set),
+    ListComp(ListComp),
+    SetComp(SetComp),
+    DictComp(DictComp),
+    GeneratorExp(GeneratorExp),
+    Await(Await),
+    Yield(Yield),
+    YieldFrom(YieldFrom),
+    Compare(Compare),
+    Call(Call),
+    FormattedValue(FormattedValue),
+    JoinedStr(JoinedStr),
+    Constant(Constant),
+    Attribute(Attribute),
+    Subscript(Subscript),
+    Starred(Starred),
+    Name(Name),
+    List(List),
+    Tuple(Tuple),
+    Slice(Slice),
+}
+
+#[derive(Clone, Debug)]
+pub struct BoolOp {
+    pub op: boolop,
+    pub values: Vec<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NamedExpr {
+    pub target: Box<expr>,
+    pub value: Box<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BinOp {
+    pub left: Box<expr>,
+    pub op: operator,
+    pub right: Box<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct UnaryOp {
+    pub op: unaryop,
+    pub operand: Box<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Lambda {
+    pub args: Box<arguments>,
+    pub body: Box<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct IfExp {
+    pub test: Box<expr>,
+    pub body: Box<expr>,
+    pub orelse: Box<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Dict {
+    pub keys: Vec<expr>,
+    pub values: Vec<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Set {
+    pub elts: Vec<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ListComp {
+    pub elt: Box<expr>,
+    pub generators: Vec<comprehension>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SetComp {
+    pub elt: Box<expr>,
+    pub generators: Vec<comprehension>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DictComp {
+    pub key: Box<expr>,
+    pub value: Box<expr>,
+    pub generators: Vec<comprehension>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct GeneratorExp {
+    pub elt: Box<expr>,
+    pub generators: Vec<comprehension>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Await {
+    pub value: Box<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Yield {
+    pub value: Option<Box<expr>>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct YieldFrom {
+    pub value: Box<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Compare {
+    pub left: Box<expr>,
+    pub ops: Vec<cmpop>,
+    pub comparators: Vec<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Call {
+    pub func: Box<expr>,
+    pub args: Vec<expr>,
+    pub keywords: Vec<keyword>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct FormattedValue {
+    pub value: Box<expr>,
+    pub conversion: i64,
+    pub format_spec: Option<Box<expr>>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct JoinedStr {
+    pub values: Vec<expr>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Constant {
+    pub value: constant,
+    pub kind: Option<String>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Attribute {
+    pub value: Box<expr>,
+    pub attr: String,
+    pub ctx: expr_context,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Subscript {
+    pub value: Box<expr>,
+    pub slice: Box<expr>,
+    pub ctx: expr_context,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Starred {
+    pub value: Box<expr>,
+    pub ctx: expr_context,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Name {
+    pub id: String,
+    pub ctx: expr_context,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct List {
+    pub elts: Vec<expr>,
+    pub ctx: expr_context,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Tuple {
+    pub elts: Vec<expr>,
+    pub ctx: expr_context,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Slice {
+    pub lower: Option<Box<expr>>,
+    pub upper: Option<Box<expr>>,
+    pub step: Option<Box<expr>>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub enum expr_context {
+    Load,
+    Store,
+    Del,
+}
+
+#[derive(Clone, Debug)]
+pub enum boolop {
+    And,
+    Or,
+}
+
+#[derive(Clone, Debug)]
+pub enum operator {
+    Add,
+    Sub,
+    Mult,
+    MatMult,
+    Div,
+    Mod,
+    Pow,
+    LShift,
+    RShift,
+    BitOr,
+    BitXor,
+    BitAnd,
+    FloorDiv,
+}
+
+#[derive(Clone, Debug)]
+pub enum unaryop {
+    Invert,
+    Not,
+    UAdd,
+    USub,
+}
+
+#[derive(Clone, Debug)]
+pub enum cmpop {
+    Eq,
+    NotEq,
+    Lt,
+    LtE,
+    Gt,
+    GtE,
+    Is,
+    IsNot,
+    In,
+    NotIn,
+}
+
+#[derive(Clone, Debug)]
+pub struct comprehension {
+    pub target: expr,
+    pub iter: expr,
+    pub ifs: Vec<expr>,
+    pub is_async: i64,
+}
+
+#[derive(Clone, Debug)]
+pub enum excepthandler {
+    ExceptHandler(ExceptHandler),
+}
+
+#[derive(Clone, Debug)]
+pub struct ExceptHandler {
+    pub r#type: Option<expr>,
+    pub name: Option<String>,
+    pub body: Vec<stmt>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct arguments {
+    pub posonlyargs: Vec<arg>,
+    pub args: Vec<arg>,
+    pub vararg: Option<Box<arg>>,
+    pub kwonlyargs: Vec<arg>,
+    pub kw_defaults: Vec<expr>,
+    pub kwarg: Option<Box<arg>>,
+    pub defaults: Vec<expr>,
+}
+
+#[derive(Clone, Debug)]
+pub struct arg {
+    pub arg: String,
+    pub annotation: Option<Box<expr>>,
+    pub type_comment: Option<String>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct keyword {
+    pub arg: Option<String>,
+    pub value: expr,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct alias {
+    pub name: String,
+    pub asname: Option<String>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: Option<i64>,
+    pub end_col_offset: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct withitem {
+    pub context_expr: expr,
+    pub optional_vars: Option<expr>,
+}
+
+#[derive(Clone, Debug)]
+pub struct match_case {
+    pub pattern: pattern,
+    pub guard: Option<expr>,
+    pub body: Vec<stmt>,
+}
+
+#[derive(Clone, Debug)]
+pub enum pattern {
+    MatchValue(MatchValue),
+    MatchSingleton(MatchSingleton),
+    MatchSequence(MatchSequence),
+    MatchMapping(MatchMapping),
+    MatchClass(MatchClass),
+    MatchStar(MatchStar),
+    MatchAs(MatchAs),
+    MatchOr(MatchOr),
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchValue {
+    pub value: expr,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: i64,
+    pub end_col_offset: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchSingleton {
+    pub value: constant,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: i64,
+    pub end_col_offset: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchSequence {
+    pub patterns: Vec<pattern>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: i64,
+    pub end_col_offset: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchMapping {
+    pub keys: Vec<expr>,
+    pub patterns: Vec<pattern>,
+    pub rest: Option<String>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: i64,
+    pub end_col_offset: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchClass {
+    pub cls: expr,
+    pub patterns: Vec<pattern>,
+    pub kwd_attrs: Vec<String>,
+    pub kwd_patterns: Vec<pattern>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: i64,
+    pub end_col_offset: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchStar {
+    pub name: Option<String>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: i64,
+    pub end_col_offset: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchAs {
+    pub pattern: Option<Box<pattern>>,
+    pub name: Option<String>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: i64,
+    pub end_col_offset: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchOr {
+    pub patterns: Vec<pattern>,
+    pub lineno: i64,
+    pub col_offset: i64,
+    pub end_lineno: i64,
+    pub end_col_offset: i64,
+}
+
+#[derive(Clone, Debug)]
+pub enum type_ignore {
+    TypeIgnore(TypeIgnore),
+}
+
+#[derive(Clone, Debug)]
+pub struct TypeIgnore {
+    pub lineno: i64,
+    pub tag: String,
+}
+
+/// ASDL 的内置类型 constant 需要自行定义
+pub type constant = String;
@@ -0,0 +1,631 @@
+export type mod = 
+    | Module
+    | Interactive
+    | Expression
+    | FunctionType
+
+export interface modBase<K extends string> {
+    $kind: K;
+}
+
+export interface Module extends modBase<"Module"> {
+    body: stmt[],
+    type_ignores: type_ignore[],
+}
+
+export interface Interactive extends modBase<"Interactive"> {
+    body: stmt[],
+}
+
+export interface Expression extends modBase<"Expression"> {
+    body: expr,
+}
+
+export interface FunctionType extends modBase<"FunctionType"> {
+    argtypes: expr[],
+    returns: expr,
+}
+
+export type stmt = 
+    | Pass
+    | Break
+    | Continue
+    | FunctionDef
+    | AsyncFunctionDef
+    | ClassDef
+    | Return
+    | Delete
+    | Assign
+    | AugAssign
+    | AnnAssign
+    | For
+    | AsyncFor
+    | While
+    | If
+    | With
+    | AsyncWith
+    | Match
+    | Raise
+    | Try
+    | TryStar
+    | Assert
+    | Import
+    | ImportFrom
+    | Global
+    | Nonlocal
+    | Expr
+
+export interface stmtBase<K extends string> {
+    $kind: K;
+    lineno: number,
+    col_offset: number,
+    end_lineno?: number,
+    end_col_offset?: number,
+}
+
+export interface Pass extends stmtBase<"Pass"> {}
+export interface Break extends stmtBase<"Break"> {}
+export interface Continue extends stmtBase<"Continue"> {}
+
+export interface FunctionDef extends stmtBase<"FunctionDef"> {
+    name: string,
+    args: arguments,
+    body: stmt[],
+    decorator_list: expr[],
+    returns?: expr,
+    type_comment?: string,
+}
+
+export interface AsyncFunctionDef extends stmtBase<"AsyncFunctionDef"> {
+    name: string,
+    args: arguments,
+    body: stmt[],
+    decorator_list: expr[],
+    returns?: expr,
+    type_comment?: string,
+}
+
+export interface ClassDef extends stmtBase<"ClassDef"> {
+    name: string,
+    bases: expr[],
+    keywords: keyword[],
+    body: stmt[],
+    decorator_list: expr[],
+}
+
+export interface Return extends stmtBase<"Return"> {
+    value?: expr,
+}
+
+export interface Delete extends stmtBase<"Delete"> {
+    targets: expr[],
+}
+
+export interface Assign extends stmtBase<"Assign"> {
+    targets: expr[],
+    value: expr,
+    type_comment?: string,
+}
+
+export interface AugAssign extends stmtBase<"AugAssign"> {
+    target: expr,
+    op: operator,
+    value: expr,
+}
+
+export interface AnnAssign extends stmtBase<"AnnAssign"> {
+    target: expr,
+    annotation: expr,
+    value?: expr,
+    simple: number,
+}
+
+export interface For extends stmtBase<"For"> {
+    target: expr,
+    iter: expr,
+    body: stmt[],
+    orelse: stmt[],
+    type_comment?: string,
+}
+
+export interface AsyncFor extends stmtBase<"AsyncFor"> {
+    target: expr,
+    iter: expr,
+    body: stmt[],
+    orelse: stmt[],
+    type_comment?: string,
+}
+
+export interface While extends stmtBase<"While"> {
+    test: expr,
+    body: stmt[],
+    orelse: stmt[],
+}
+
+export interface If extends stmtBase<"If"> {
+    test: expr,
+    body: stmt[],
+    orelse: stmt[],
+}
+
+export interface With extends stmtBase<"With"> {
+    items: withitem[],
+    body: stmt[],
+    type_comment?: string,
+}
+
+export interface AsyncWith extends stmtBase<"AsyncWith"> {
+    items: withitem[],
+    body: stmt[],
+    type_comment?: string,
+}
+
+export interface Match extends stmtBase<"Match"> {
+    subject: expr,
+    cases: match_case[],
+}
+
+export interface Raise extends stmtBase<"Raise"> {
+    exc?: expr,
+    cause?: expr,
+}
+
+export interface Try extends stmtBase<"Try"> {
+    body: stmt[],
+    handlers: excepthandler[],
+    orelse: stmt[],
+    finalbody: stmt[],
+}
+
+export interface TryStar extends stmtBase<"TryStar"> {
+    body: stmt[],
+    handlers: excepthandler[],
+    orelse: stmt[],
+    finalbody: stmt[],
+}
+
+export interface Assert extends stmtBase<"Assert"> {
+    test: expr,
+    msg?: expr,
+}
+
+export interface Import extends stmtBase<"Import"> {
+    names: alias[],
+}
+
+export interface ImportFrom extends stmtBase<"ImportFrom"> {
+    module?: string,
+    names: alias[],
+    level?: number,
+}
+
+export interface Global extends stmtBase<"Global"> {
+    names: string[],
+}
+
+export interface Nonlocal extends stmtBase<"Nonlocal"> {
+    names: string[],
+}
+
+export interface Expr extends stmtBase<"Expr"> {
+    value: expr,
+}
+
+export type expr = 
+    | BoolOp
+    | NamedExpr
+    | BinOp
+    | UnaryOp
+    | Lambda
+    | IfExp
+    | Dict
+    | Set
+    | ListComp
+    | SetComp
+    | DictComp
+    | GeneratorExp
+    | Await
+    | Yield
+    | YieldFrom
+    | Compare
+    | Call
+    | FormattedValue
+    | JoinedStr
+    | Constant
+    | Attribute
+    | Subscript
+    | Starred
+    | Name
+    | List
+    | Tuple
+    | Slice
+
+export interface exprBase<K extends string> {
+    $kind: K;
+    lineno: number,
+    col_offset: number,
+    end_lineno?: number,
+    end_col_offset?: number,
+}
+
+export interface BoolOp extends exprBase<"BoolOp"> {
+    op: boolop,
+    values: expr[],
+}
+
+export interface NamedExpr extends exprBase<"NamedExpr"> {
+    target: expr,
+    value: expr,
+}
+
+export interface BinOp extends exprBase<"BinOp"> {
+    left: expr,
+    op: operator,
+    right: expr,
+}
+
+export interface UnaryOp extends exprBase<"UnaryOp"> {
+    op: unaryop,
+    operand: expr,
+}
+
+export interface Lambda extends exprBase<"Lambda"> {
+    args: arguments,
+    body: expr,
+}
+
+export interface IfExp extends exprBase<"IfExp"> {
+    test: expr,
+    body: expr,
+    orelse: expr,
+}
+
+export interface Dict extends exprBase<"Dict"> {
+    keys: expr[],
+    values: expr[],
+}
+
+export interface Set extends exprBase<"Set"> {
+    elts: expr[],
+}
+
+export interface ListComp extends exprBase<"ListComp"> {
+    elt: expr,
+    generators: comprehension[],
+}
+
+export interface SetComp extends exprBase<"SetComp"> {
+    elt: expr,
+    generators: comprehension[],
+}
+
+export interface DictComp extends exprBase<"DictComp"> {
+    key: expr,
+    value: expr,
+    generators: comprehension[],
+}
+
+export interface GeneratorExp extends exprBase<"GeneratorExp"> {
+    elt: expr,
+    generators: comprehension[],
+}
+
+export interface Await extends exprBase<"Await"> {
+    value: expr,
+}
+
+export interface Yield extends exprBase<"Yield"> {
+    value?: expr,
+}
+
+export interface YieldFrom extends exprBase<"YieldFrom"> {
+    value: expr,
+}
+
+export interface Compare extends exprBase<"Compare"> {
+    left: expr,
+    ops: cmpop[],
+    comparators: expr[],
+}
+
+export interface Call extends exprBase<"Call"> {
+    func: expr,
+    args: expr[],
+    keywords: keyword[],
+}
+
+export interface FormattedValue extends exprBase<"FormattedValue"> {
+    value: expr,
+    conversion: number,
+    format_spec?: expr,
+}
+
+export interface JoinedStr extends exprBase<"JoinedStr"> {
+    values: expr[],
+}
+
+export interface Constant extends exprBase<"Constant"> {
+    value: constant,
+    kind?: string,
+}
+
+export interface Attribute extends exprBase<"Attribute"> {
+    value: expr,
+    attr: string,
+    ctx: expr_context,
+}
+
+export interface Subscript extends exprBase<"Subscript"> {
+    value: expr,
+    slice: expr,
+    ctx: expr_context,
+}
+
+export interface Starred extends exprBase<"Starred"> {
+    value: expr,
+    ctx: expr_context,
+}
+
+export interface Name extends exprBase<"Name"> {
+    id: string,
+    ctx: expr_context,
+}
+
+export interface List extends exprBase<"List"> {
+    elts: expr[],
+    ctx: expr_context,
+}
+
+export interface Tuple extends exprBase<"Tuple"> {
+    elts: expr[],
+    ctx: expr_context,
+}
+
+export interface Slice extends exprBase<"Slice"> {
+    lower?: expr,
+    upper?: expr,
+    step?: expr,
+}
+
+export type expr_context = 
+    | Load
+    | Store
+    | Del
+
+export interface expr_contextBase<K extends string> {
+    $kind: K;
+}
+
+export interface Load extends expr_contextBase<"Load"> {}
+export interface Store extends expr_contextBase<"Store"> {}
+export interface Del extends expr_contextBase<"Del"> {}
+
+export type boolop = 
+    | And
+    | Or
+
+export interface boolopBase<K extends string> {
+    $kind: K;
+}
+
+export interface And extends boolopBase<"And"> {}
+export interface Or extends boolopBase<"Or"> {}
+
+export type operator = 
+    | Add
+    | Sub
+    | Mult
+    | MatMult
+    | Div
+    | Mod
+    | Pow
+    | LShift
+    | RShift
+    | BitOr
+    | BitXor
+    | BitAnd
+    | FloorDiv
+
+export interface operatorBase<K extends string> {
+    $kind: K;
+}
+
+export interface Add extends operatorBase<"Add"> {}
+export interface Sub extends operatorBase<"Sub"> {}
+export interface Mult extends operatorBase<"Mult"> {}
+export interface MatMult extends operatorBase<"MatMult"> {}
+export interface Div extends operatorBase<"Div"> {}
+export interface Mod extends operatorBase<"Mod"> {}
+export interface Pow extends operatorBase<"Pow"> {}
+export interface LShift extends operatorBase<"LShift"> {}
+export interface RShift extends operatorBase<"RShift"> {}
+export interface BitOr extends operatorBase<"BitOr"> {}
+export interface BitXor extends operatorBase<"BitXor"> {}
+export interface BitAnd extends operatorBase<"BitAnd"> {}
+export interface FloorDiv extends operatorBase<"FloorDiv"> {}
+
+export type unaryop = 
+    | Invert
+    | Not
+    | UAdd
+    | USub
+
+export interface unaryopBase<K extends string> {
+    $kind: K;
+}
+
+export interface Invert extends unaryopBase<"Invert"> {}
+export interface Not extends unaryopBase<"Not"> {}
+export interface UAdd extends unaryopBase<"UAdd"> {}
+export interface USub extends unaryopBase<"USub"> {}
+
+export type cmpop = 
+    | Eq
+    | NotEq
+    | Lt
+    | LtE
+    | Gt
+    | GtE
+    | Is
+    | IsNot
+    | In
+    | NotIn
+
+export interface cmpopBase<K extends string> {
+    $kind: K;
+}
+
+export interface Eq extends cmpopBase<"Eq"> {}
+export interface NotEq extends cmpopBase<"NotEq"> {}
+export interface Lt extends cmpopBase<"Lt"> {}
+export interface LtE extends cmpopBase<"LtE"> {}
+export interface Gt extends cmpopBase<"Gt"> {}
+export interface GtE extends cmpopBase<"GtE"> {}
+export interface Is extends cmpopBase<"Is"> {}
+export interface IsNot extends cmpopBase<"IsNot"> {}
+export interface In extends cmpopBase<"In"> {}
+export interface NotIn extends cmpopBase<"NotIn"> {}
+
+export interface comprehension {
+    target: expr,
+    iter: expr,
+    ifs: expr[],
+    is_async: number,
+}
+
+export type excepthandler = 
+    | ExceptHandler
+
+export interface excepthandlerBase<K extends string> {
+    $kind: K;
+    lineno: number,
+    col_offset: number,
+    end_lineno?: number,
+    end_col_offset?: number,
+}
+
+export interface ExceptHandler extends excepthandlerBase<"ExceptHandler"> {
+    type?: expr,
+    name?: string,
+    body: stmt[],
+}
+
+export interface arguments {
+    posonlyargs: arg[],
+    args: arg[],
+    vararg?: arg,
+    kwonlyargs: arg[],
+    kw_defaults: expr[],
+    kwarg?: arg,
+    defaults: expr[],
+}
+
+export interface arg {
+    arg: string,
+    annotation?: expr,
+    type_comment?: string,
+    lineno: number,
+    col_offset: number,
+    end_lineno?: number,
+    end_col_offset?: number,
+}
+
+export interface keyword {
+    arg?: string,
+    value: expr,
+    lineno: number,
+    col_offset: number,
+    end_lineno?: number,
+    end_col_offset?: number,
+}
+
+export interface alias {
+    name: string,
+    asname?: string,
+    lineno: number,
+    col_offset: number,
+    end_lineno?: number,
+    end_col_offset?: number,
+}
+
+export interface withitem {
+    context_expr: expr,
+    optional_vars?: expr,
+}
+
+export interface match_case {
+    pattern: pattern,
+    guard?: expr,
+    body: stmt[],
+}
+
+export type pattern = 
+    | MatchValue
+    | MatchSingleton
+    | MatchSequence
+    | MatchMapping
+    | MatchClass
+    | MatchStar
+    | MatchAs
+    | MatchOr
+
+export interface patternBase<K extends string> {
+    $kind: K;
+    lineno: number,
+    col_offset: number,
+    end_lineno: number,
+    end_col_offset: number,
+}
+
+export interface MatchValue extends patternBase<"MatchValue"> {
+    value: expr,
+}
+
+export interface MatchSingleton extends patternBase<"MatchSingleton"> {
+    value: constant,
+}
+
+export interface MatchSequence extends patternBase<"MatchSequence"> {
+    patterns: pattern[],
+}
+
+export interface MatchMapping extends patternBase<"MatchMapping"> {
+    keys: expr[],
+    patterns: pattern[],
+    rest?: string,
+}
+
+export interface MatchClass extends patternBase<"MatchClass"> {
+    cls: expr,
+    patterns: pattern[],
+    kwd_attrs: string[],
+    kwd_patterns: pattern[],
+}
+
+export interface MatchStar extends patternBase<"MatchStar"> {
+    name?: string,
+}
+
+export interface MatchAs extends patternBase<"MatchAs"> {
+    pattern?: pattern,
+    name?: string,
+}
+
+export interface MatchOr extends patternBase<"MatchOr"> {
+    patterns: pattern[],
+}
+
+export type type_ignore = 
+    | TypeIgnore
+
+export interface type_ignoreBase<K extends string> {
+    $kind: K;
+}
+
+export interface TypeIgnore extends type_ignoreBase<"TypeIgnore"> {
+    lineno: number,
+    tag: string,
+}
+
+/** ASDL 的内置类型 constant 需要自行定义 */
+export type constant = unknown;
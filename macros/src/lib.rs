@@ -0,0 +1,173 @@
+//! RSDL 的过程宏
+//!
+//! 这个 crate 提供了两个过程宏，它们在编译期运行 RSDL 编译器和 Rust 代码生成器，
+//! 并将生成的代码直接展开到宏调用的位置，无需构建脚本，也无需将生成的文件提交到仓库中：
+//!
+//! - [`include_rsdl!`] - 编译一个 RSDL 文件，路径相对于 crate 的根目录
+//! - [`rsdl!`] - 编译宏调用中内联的 RSDL 代码
+//!
+//! 由于过程宏 crate 需要依赖 `rsdl` 本身，`rsdl` 无法反过来重新导出这两个宏，
+//! 请直接通过 `rsdl_macros::include_rsdl!` 和 `rsdl_macros::rsdl!` 使用。
+//!
+//! 生成的代码与命令行生成的代码完全一致，因此如果使用了 `rust_serde` 等注解，需要自行在宏调用的
+//! 作用域中导入 `Serialize`、`Deserialize` 等名称。`map` 和 `set` 类型使用完整的路径，不需要导入。
+//!
+//! # 示例
+//!
+//! ```rust
+//! rsdl_macros::rsdl! {
+//!     [rust_derive(Debug, Clone, PartialEq)]
+//!     Point(x: int, y: int)
+//! }
+//!
+//! let p = Point { x: 1, y: 2 };
+//! assert_eq!(p.clone(), p);
+//! ```
+//!
+//! 如果内联的代码中含有注释或者 `#include` 指令，请将代码写在字符串字面量中，
+//! 因为 Rust 的词法分析器会改变这些内容：
+//!
+//! ```rust
+//! rsdl_macros::rsdl!(r#"
+//!     --- a two dimensional point
+//!     Point(x: int, y: int)
+//! "#);
+//! ```
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::LitStr;
+
+use rsdl::codegen::rustgen::RustGeneratorFactory;
+use rsdl::compiler::{
+    compile_with_source_map,
+    CompileOptions,
+    CompileOutput,
+    FileProvider,
+    FsFileProvider
+};
+use rsdl::diagnostic::SourceMap;
+
+/// 编译一个 RSDL 文件，并将生成的 Rust 代码展开到宏调用的位置
+///
+/// 文件路径相对于当前 crate 的根目录（即 `CARGO_MANIFEST_DIR`）。
+/// 编译失败时，错误信息会指向宏调用中的文件路径
+///
+/// # 示例
+///
+/// ```rust,ignore
+/// rsdl_macros::include_rsdl!("schema/ast.rsdl");
+/// ```
+#[proc_macro]
+pub fn include_rsdl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let path = syn::parse_macro_input!(input as LitStr);
+    let input = manifest_dir().join(path.value());
+
+    expand(&input, &FsFileProvider, path.span()).into()
+}
+
+/// 编译宏调用中内联的 RSDL 代码，并将生成的 Rust 代码展开到宏调用的位置
+///
+/// 宏的参数可以是 RSDL 代码本身，也可以是包含 RSDL 代码的字符串字面量。
+/// 内联代码中的 `#include` 路径相对于当前 crate 的根目录
+#[proc_macro]
+pub fn rsdl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let (src, span) = match syn::parse::<LitStr>(input.clone()) {
+        Ok(lit) => (lit.value(), lit.span()),
+        Err(_) => {
+            let tokens = TokenStream::from(input);
+            let span = tokens
+                .clone()
+                .into_iter()
+                .next()
+                .map(|token| token.span())
+                .unwrap_or_else(Span::call_site);
+            (tokens.to_string(), span)
+        }
+    };
+
+    let files = InlineFileProvider {
+        path: manifest_dir().join("(rsdl!)"),
+        src
+    };
+
+    expand(&files.path, &files, span).into()
+}
+
+fn manifest_dir() -> PathBuf {
+    std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+}
+
+fn expand(input: &Path, files: &dyn FileProvider, span: Span) -> TokenStream {
+    let mut options = CompileOptions::new(input);
+    options.targets.push("rust".to_string());
+
+    let mut source_map = SourceMap::new();
+    let output = match compile_with_source_map(
+        options,
+        files,
+        &[&RustGeneratorFactory()],
+        &mut source_map
+    ) {
+        Ok(output) => output,
+        Err(diags) => {
+            let message = format!("RSDL 编译失败:\n{}", diags.render(&source_map));
+            return syn::Error::new(span, message).to_compile_error();
+        }
+    };
+
+    let CompileOutput { outputs, files: read_files, .. } = output;
+    let code = outputs
+        .into_iter()
+        .next()
+        .map(|target_output| target_output.code)
+        .unwrap_or_default();
+
+    let items = match code.parse::<TokenStream>() {
+        Ok(items) => items,
+        Err(e) => {
+            let message = format!("RSDL 生成的代码无法被解析为 Rust 代码: {}", e);
+            return syn::Error::new(span, message).to_compile_error();
+        }
+    };
+
+    // 让 rustc 在 RSDL 文件发生变化时重新展开宏
+    let tracked_files = read_files
+        .iter()
+        .filter(|path| path.is_file())
+        .map(|path| path.to_string_lossy().to_string());
+
+    quote! {
+        #(const _: &[u8] = include_bytes!(#tracked_files);)*
+        #items
+    }
+}
+
+/// 为内联代码提供一个虚拟的根文件，其他文件从文件系统读取
+struct InlineFileProvider {
+    path: PathBuf,
+    src: String
+}
+
+impl FileProvider for InlineFileProvider {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        if path == self.path {
+            Ok(self.src.clone())
+        } else {
+            FsFileProvider.read(path)
+        }
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        if path == self.path {
+            Ok(self.path.clone())
+        } else {
+            FsFileProvider.canonicalize(path)
+        }
+    }
+}
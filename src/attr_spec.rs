@@ -0,0 +1,243 @@
+//! 注解的声明与校验
+//!
+//! 每个代码生成器都可以通过 [`crate::codegen::CodeGenerator::known_attrs`] 声明自己能够理解的注解，
+//! 包括注解的名称、允许出现的位置以及参数的形式。`rsdl` 编译器会使用 [`CORE_ATTRS`]
+//! 和所有已加载的代码生成器声明的注解来校验源代码中的每一个注解：
+//!
+//! - 未知的注解会产生一条警告，并在可能的情况下给出拼写建议
+//! - 出现在不允许的位置上的注解会产生一条警告
+//! - 参数形式不正确的注解会产生一条错误
+
+use std::fmt::{Display, Formatter};
+
+use crate::diagnostic::{Diagnostic, Diagnostics};
+use crate::parser::hir::{Attr, AttrItem};
+
+/// 注解可以出现的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttrPosition {
+    /// 类型定义，包括类型别名、简单类型、和类型以及全局注解
+    Type,
+    /// 字段
+    Field,
+    /// 和类型的标量变体
+    Variant,
+    /// 和类型的构造器
+    Ctor
+}
+
+impl Display for AttrPosition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            AttrPosition::Type => "类型定义",
+            AttrPosition::Field => "字段",
+            AttrPosition::Variant => "标量变体",
+            AttrPosition::Ctor => "构造器"
+        })
+    }
+}
+
+/// 注解参数的形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttrShape {
+    /// 不带参数的标识符，例如 `[boxed]`
+    Flag,
+    /// 一个字符串参数，例如 `[doc("...")]` 或者 `[doc = "..."]`
+    String,
+    /// 调用形式，参数的数量和内容不限，例如 `[rust_derive(Debug, Clone)]`
+    Call,
+    /// 不带参数的标识符，或者参数都是标识符的调用形式，例如 `[no_inherit]` 和 `[no_inherit(private)]`
+    FlagOrIdents,
+    /// 不限制形式
+    Any
+}
+
+impl AttrShape {
+    /// 检查一个注解项是否符合这个形式
+    pub fn matches(&self, item: &AttrItem) -> bool {
+        match (self, item) {
+            (AttrShape::Any, _) => true,
+            (AttrShape::Flag, AttrItem::Identifier(_)) => true,
+            (AttrShape::String, AttrItem::CallAlike(_, args)) => {
+                matches!(args.as_slice(), [AttrItem::String(_)])
+            },
+            (AttrShape::String, AttrItem::Assignment(_, value)) => {
+                matches!(value.as_ref(), AttrItem::String(_))
+            },
+            (AttrShape::Call, AttrItem::CallAlike(_, _)) => true,
+            (AttrShape::FlagOrIdents, AttrItem::Identifier(_)) => true,
+            (AttrShape::FlagOrIdents, AttrItem::CallAlike(_, args)) => {
+                args.iter().all(|arg| matches!(arg, AttrItem::Identifier(_)))
+            },
+            _ => false
+        }
+    }
+
+    /// 这个形式的书写示例，用于错误信息
+    fn example(&self, name: &str) -> String {
+        match self {
+            AttrShape::Flag => format!("[{}]", name),
+            AttrShape::String => format!("[{}(\"...\")] 或者 [{} = \"...\"]", name, name),
+            AttrShape::Call => format!("[{}(...)]", name),
+            AttrShape::FlagOrIdents => format!("[{}] 或者 [{}(name, ...)]", name, name),
+            AttrShape::Any => format!("[{}]", name)
+        }
+    }
+}
+
+/// 一个注解的声明
+#[derive(Debug, Clone, Copy)]
+pub struct AttrSpec {
+    /// 注解的名称
+    pub name: &'static str,
+    /// 注解允许出现的位置
+    pub positions: &'static [AttrPosition],
+    /// 注解参数的形式
+    pub shape: AttrShape
+}
+
+impl AttrSpec {
+    /// 创建一个注解声明
+    pub const fn new(
+        name: &'static str,
+        positions: &'static [AttrPosition],
+        shape: AttrShape
+    ) -> Self {
+        Self { name, positions, shape }
+    }
+}
+
+/// 所有位置
+pub const ALL_POSITIONS: &[AttrPosition] = &[
+    AttrPosition::Type,
+    AttrPosition::Field,
+    AttrPosition::Variant,
+    AttrPosition::Ctor
+];
+
+/// `rsdl` 编译器本身理解的注解，与代码生成器无关
+pub const CORE_ATTRS: &[AttrSpec] = &[
+    AttrSpec::new("doc", ALL_POSITIONS, AttrShape::String),
+    AttrSpec::new("doc_ctor", &[AttrPosition::Variant, AttrPosition::Ctor], AttrShape::String),
+    AttrSpec::new("boxed", &[AttrPosition::Field, AttrPosition::Ctor], AttrShape::Flag),
+    AttrSpec::new("private", &[AttrPosition::Type, AttrPosition::Field, AttrPosition::Ctor], AttrShape::Flag),
+    AttrSpec::new("inline", &[AttrPosition::Type], AttrShape::Flag),
+    AttrSpec::new("no_inherit", &[AttrPosition::Type], AttrShape::FlagOrIdents),
+    AttrSpec::new("repr", &[AttrPosition::Type], AttrShape::Call),
+    AttrSpec::new("discriminant", &[AttrPosition::Type], AttrShape::String),
+    AttrSpec::new("content", &[AttrPosition::Type], AttrShape::String),
+    AttrSpec::new("tag_value", &[AttrPosition::Variant, AttrPosition::Ctor], AttrShape::String),
+    AttrSpec::new("rename_all", &[AttrPosition::Type, AttrPosition::Ctor], AttrShape::Call),
+    AttrSpec::new("rename", &[AttrPosition::Field], AttrShape::String),
+    AttrSpec::new("rename", &[AttrPosition::Field], AttrShape::Call)
+];
+
+/// 使用注解声明校验一组注解，将发现的问题加入 `diags`
+pub(crate) fn check_attrs(
+    attr_list: &[Attr],
+    position: AttrPosition,
+    known_attrs: &[AttrSpec],
+    diags: &mut Diagnostics
+) {
+    for attr in attr_list {
+        let Some(name) = attr.item.name() else {
+            diags.push(Diagnostic::warning(attr.span.clone(), "无法识别的注解，注解应该以名称开头"));
+            continue;
+        };
+
+        let specs = known_attrs
+            .iter()
+            .filter(|spec| spec.name == name)
+            .collect::<Vec<_>>();
+
+        if specs.is_empty() {
+            let message = match suggest(name, known_attrs) {
+                Some(suggestion) => format!("未知的注解 {}，你是否想要使用 {}？", name, suggestion),
+                None => format!("未知的注解 {}", name)
+            };
+            diags.push(Diagnostic::warning(attr.span.clone(), message));
+            continue;
+        }
+
+        if !specs.iter().any(|spec| spec.shape.matches(&attr.item)) {
+            let examples = specs
+                .iter()
+                .map(|spec| spec.shape.example(name))
+                .collect::<Vec<_>>()
+                .join(" 或者 ");
+            diags.push(Diagnostic::new(
+                attr.span.clone(),
+                format!("注解 {} 的形式不正确，应该写作 {}", name, examples)
+            ));
+            continue;
+        }
+
+        if !specs.iter().any(|spec| spec.positions.contains(&position)) {
+            diags.push(Diagnostic::warning(
+                attr.span.clone(),
+                format!("注解 {} 不能用于{}，它会被忽略", name, position)
+            ));
+        }
+    }
+}
+
+/// 在已知的注解中寻找与 `name` 最相近的名称
+fn suggest(name: &str, known_attrs: &[AttrSpec]) -> Option<&'static str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    known_attrs
+        .iter()
+        .map(|spec| (edit_distance(name, spec.name), spec.name))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name)
+}
+
+/// 计算两个字符串之间的 Levenshtein 距离
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    let mut cur = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        cur[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + if ca == *cb { 0 } else { 1 };
+            cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::edit_distance;
+    use crate::compiler::testing::{compile_err, compile_warnings};
+
+    #[test]
+    fn private_constructor_is_not_warned() {
+        let warnings = compile_warnings("Shape: [private] Circle(r: float) | Empty");
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+
+    #[test]
+    fn misplaced_and_unknown_attrs_are_warned() {
+        let warnings = compile_warnings("[tag_value(\"a\")] A([bxoed] x: int)");
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(warnings[1].contains("bxoed"), "{:?}", warnings);
+    }
+
+    #[test]
+    fn malformed_attr_is_an_error() {
+        let errors = compile_err("[doc(1)] A(x: int)", "rust");
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+    }
+
+    #[test]
+    fn edit_distance_counts_edits() {
+        assert_eq!(edit_distance("bxoed", "boxed"), 2);
+        assert_eq!(edit_distance("doc", "doc_ctor"), 5);
+    }
+}
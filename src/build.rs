@@ -0,0 +1,172 @@
+//! 在 Cargo 构建脚本（`build.rs`）中编译 RSDL 文件
+//!
+//! # 示例
+//!
+//! ```rust,no_run
+//! // build.rs
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     rsdl::build::Builder::new()
+//!         .input("ast.rsdl")
+//!         .target("rust")
+//!         .namespace("ast")
+//!         .compile()?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! ```rust,ignore
+//! // src/lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/ast.rs"));
+//! ```
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::codegen::CodeGeneratorFactory;
+use crate::codegen::rustgen::RustGeneratorFactory;
+use crate::codegen::tsgen::TSInterfaceGeneratorFactory;
+use crate::compiler::{compile_with_source_map, CompileOptions, FsFileProvider};
+use crate::diagnostic::SourceMap;
+
+/// 构建脚本中使用的 RSDL 编译器
+///
+/// 生成的代码会被写入 `OUT_DIR` 目录，文件名默认为根输入文件的文件名加上目标语言的扩展名。
+/// 编译器还会为根输入文件、所有 `#include` 的文件以及自定义的标准库输出
+/// `cargo:rerun-if-changed`，这样在这些文件发生变化时 Cargo 会自动重新生成代码
+#[derive(Debug, Clone)]
+pub struct Builder {
+    input: Option<PathBuf>,
+    target: String,
+    namespace: Option<String>,
+    discriminant: Option<String>,
+    stdlib: Option<PathBuf>,
+    out_dir: Option<PathBuf>,
+    output_file_name: Option<String>
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Builder {
+    /// 创建一个以 Rust 为目标语言的编译器
+    pub fn new() -> Self {
+        Self {
+            input: None,
+            target: "rust".to_string(),
+            namespace: None,
+            discriminant: None,
+            stdlib: None,
+            out_dir: None,
+            output_file_name: None
+        }
+    }
+
+    /// 设置根输入文件，相对路径相对于 crate 的根目录
+    pub fn input(mut self, input: impl AsRef<Path>) -> Self {
+        self.input = Some(input.as_ref().to_path_buf());
+        self
+    }
+
+    /// 设置目标语言，可以是 `rust` 或者 `typescript`
+    pub fn target(mut self, target: impl ToString) -> Self {
+        self.target = target.to_string();
+        self
+    }
+
+    /// 设置生成代码所在的名称空间
+    pub fn namespace(mut self, namespace: impl ToString) -> Self {
+        self.namespace = Some(namespace.to_string());
+        self
+    }
+
+    /// 设置和类型的 `discriminant` 名称，默认为 `$kind`
+    pub fn discriminant(mut self, discriminant: impl ToString) -> Self {
+        self.discriminant = Some(discriminant.to_string());
+        self
+    }
+
+    /// 使用自定义的标准库文件代替 [`crate::driver::REFERENTIAL_STDLIB`]
+    pub fn stdlib(mut self, stdlib: impl AsRef<Path>) -> Self {
+        self.stdlib = Some(stdlib.as_ref().to_path_buf());
+        self
+    }
+
+    /// 设置输出目录，默认为 `OUT_DIR` 环境变量指定的目录
+    pub fn out_dir(mut self, out_dir: impl AsRef<Path>) -> Self {
+        self.out_dir = Some(out_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// 设置输出文件名，默认为根输入文件的文件名加上目标语言的扩展名
+    pub fn output_file_name(mut self, file_name: impl ToString) -> Self {
+        self.output_file_name = Some(file_name.to_string());
+        self
+    }
+
+    /// 编译 RSDL 文件并写入输出目录，返回输出文件的路径
+    ///
+    /// 编译失败时，带有代码片段的错误信息会被输出到标准错误
+    pub fn compile(self) -> Result<PathBuf, Box<dyn Error>> {
+        let input = self.input.ok_or("没有指定输入文件")?;
+        let out_dir = match self.out_dir {
+            Some(out_dir) => out_dir,
+            None => PathBuf::from(std::env::var_os("OUT_DIR").ok_or("没有设置 OUT_DIR 环境变量")?)
+        };
+
+        let extension = match self.target.as_str() {
+            "rust" => "rs",
+            "typescript" => "ts",
+            target => return Err(format!("不支持的输出模式 {}", target).into())
+        };
+        let output_file_name = match self.output_file_name {
+            Some(output_file_name) => output_file_name,
+            None => {
+                let stem = input.file_stem().ok_or("输入文件名无效")?;
+                format!("{}.{}", stem.to_string_lossy(), extension)
+            }
+        };
+
+        let mut options = CompileOptions::new(&input);
+        options.targets.push(self.target);
+        options.namespace = self.namespace;
+        if let Some(discriminant) = self.discriminant {
+            options.discriminant = discriminant;
+        }
+        options.stdlib = self.stdlib;
+
+        let generators: [&dyn CodeGeneratorFactory; 2] = [
+            &RustGeneratorFactory(),
+            &TSInterfaceGeneratorFactory()
+        ];
+
+        let mut source_map = SourceMap::new();
+        let output = match compile_with_source_map(options, &FsFileProvider, &generators, &mut source_map) {
+            Ok(output) => output,
+            Err(diags) => {
+                eprintln!("{}", diags.render(&source_map));
+                return Err(format!("RSDL 编译失败，共 {} 个错误", diags.error_count()).into());
+            }
+        };
+
+        for warning in output.warnings.iter() {
+            println!("cargo:warning={}", warning);
+        }
+
+        for file in output.files.iter() {
+            println!("cargo:rerun-if-changed={}", file.display());
+        }
+
+        let output_path = out_dir.join(output_file_name);
+        let code = output.outputs
+            .into_iter()
+            .next()
+            .map(|target_output| target_output.code)
+            .unwrap_or_default();
+        std::fs::write(&output_path, code)?;
+
+        Ok(output_path)
+    }
+}
@@ -6,13 +6,16 @@ pub mod tsgen;
 
 use std::collections::HashSet;
 use std::error::Error;
+use std::fmt::{Display, Formatter};
 use std::ops::Deref;
 
-use tracing::error;
-
+use crate::attr_spec::AttrSpec;
+use crate::diagnostic::{Diagnostic, Diagnostics, Severity, Span};
 use crate::min_resolv::ResolveContext;
+use crate::naming::{rename_rule, wire_name, RenameRule};
 use crate::parser::hir::{
-    AttrItem,
+    Attr,
+    Field,
     RSDLType,
     SumType,
     TypeConstructor,
@@ -45,7 +48,7 @@ use crate::parser::hir::{
 /// let mut sub_doc2 = Doc::new(4);
 /// sub_doc2.push_str("Bonjour le monde");
 /// sub_doc2.push_empty_line();
-/// let user_name = some_user_input();
+/// let user_name = "RSDL";
 /// sub_doc2.push_string(format!("Hello, {}", user_name));
 ///
 /// sub_doc.push_doc(Box::new(sub_doc2));
@@ -61,7 +64,7 @@ use crate::parser::hir::{
 ///     Hola Mundo
 ///         Bonjour le monde
 ///
-///         Hello, (user input)
+///         Hello, RSDL
 /// ```
 #[derive(Debug)]
 pub struct Doc {
@@ -117,11 +120,11 @@ impl Doc {
     }
 }
 
-impl ToString for Doc {
-    fn to_string(&self) -> String {
+impl Display for Doc {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let mut buf = String::new();
         doc_to_string_impl(&mut buf, self.indent, self);
-        buf
+        f.write_str(&buf)
     }
 }
 
@@ -133,12 +136,12 @@ fn doc_to_string_impl(buf: &mut String, indent: u32, doc: &Doc) {
             DocItem::Text(s) => {
                 buf.push_str(&indent_str);
                 buf.push_str(s);
-                buf.push_str("\n");
+                buf.push('\n');
             },
             DocItem::TextLiteral(s) => {
                 buf.push_str(&indent_str);
                 buf.push_str(s);
-                buf.push_str("\n");
+                buf.push('\n');
             },
             DocItem::EmptyLine => {
                 if idx + 1 == doc.items.len() {
@@ -162,6 +165,17 @@ pub enum DocItem {
     DocBlock(Box<Doc>),
 }
 
+/// 标识符在生成的代码中所处的位置，不同位置上的保留标识符可能有不同的转义方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdentContext {
+    /// 类型名称，包括和类型的构造器对应的类型
+    TypeName,
+    /// 字段名称
+    FieldName,
+    /// 命名空间名称
+    Namespace
+}
+
 /// 代码生成器 `trait`
 ///
 /// 下游代码可以实现此 `trait` 来编写自己的代码生成器。代码生成器会按照如下顺序，
@@ -176,6 +190,9 @@ pub enum DocItem {
 ///         - `visit_sum_type_scalar_variant`
 ///         - `visit_sum_type_ctor`
 /// - `visit_namespace_end`
+///
+/// 传递给 `visit_type_alias`、`visit_simple_type` 和 `visit_sum_type` 的注解已经合并了全局注解，
+/// 参见 [`ResolveContext::effective_attr`]
 pub trait CodeGenerator {
     /// 报告代码生成器的用户可见名称
     ///
@@ -188,9 +205,47 @@ pub trait CodeGenerator {
     /// 报告语言中的保留标识符
     ///
     /// 在执行代码生成之前，`rsdl` 编译器会检查即将生成的代码中是否包含保留标识符，
-    /// 并且提前给出错误信息
+    /// 对于无法转义（参见 [`CodeGenerator::escape_ident`]）的保留标识符提前给出错误信息
     fn reserved_idents(&self) -> &[&'static str];
 
+    /// 转义在 `context` 中与保留标识符冲突的标识符 `ident`
+    ///
+    /// 返回 `None` 表示无法转义，此时 `rsdl` 编译器会报告错误。
+    /// 代码生成器需要在生成代码时自行使用转义后的标识符，参见 [`CodeGenerator::target_ident`]。
+    /// 默认的实现不转义任何标识符
+    fn escape_ident(&self, _context: IdentContext, _ident: &str) -> Option<String> {
+        None
+    }
+
+    /// 标识符 `ident` 在生成的代码中的写法：保留标识符会被转义，其他标识符原样返回
+    fn target_ident(&self, context: IdentContext, ident: &str) -> String {
+        if self.reserved_idents().contains(&ident) {
+            if let Some(escaped) = self.escape_ident(context, ident) {
+                return escaped;
+            }
+        }
+        ident.to_string()
+    }
+
+    /// 报告代码生成器能够理解的注解
+    ///
+    /// `rsdl` 编译器会使用 [`crate::attr_spec::CORE_ATTRS`] 以及所有已加载的代码生成器报告的注解来校验源代码中的注解，
+    /// 未知的注解会产生警告。默认的实现不报告任何注解
+    fn known_attrs(&self) -> &[AttrSpec] {
+        &[]
+    }
+
+    /// 报告字段在生成的代码中的名称
+    ///
+    /// `rule` 是字段所在的类型（或者构造器）上 `rename_all` 注解指定的命名风格。
+    /// 默认的实现直接使用字段的线上名称（参见 [`crate::naming::wire_name`]），
+    /// 这样生成的代码无需额外处理就能与其他语言生成的代码交换数据。
+    /// 如果代码生成器使用了其他的名称，它需要自行保证序列化时使用线上名称，
+    /// 同时应该尊重 [`crate::naming::target_name`] 为本语言指定的名称
+    fn field_name(&self, rule: Option<RenameRule>, field: &Field) -> String {
+        wire_name(rule, field)
+    }
+
     /// 进入名称空间时代码生成器的行为
     ///
     /// 当用户通过命令行指定了 `--namespace` 参数时，`rsdl` 编译器会在开始生成代码时
@@ -219,7 +274,7 @@ pub trait CodeGenerator {
     fn visit_type_alias(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         alias_name: &str,
         target_type: &RSDLType,
         output: &mut Doc
@@ -229,7 +284,7 @@ pub trait CodeGenerator {
     fn visit_simple_type(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         type_ctor: &TypeConstructor,
         output: &mut Doc
     ) -> Result<(), Box<dyn Error>>;
@@ -244,7 +299,7 @@ pub trait CodeGenerator {
     fn visit_sum_type(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         sum_type: &SumType,
         output: &mut Doc
     ) -> Result<(), Box<dyn Error>>;
@@ -253,7 +308,7 @@ pub trait CodeGenerator {
     fn visit_sum_type_ctor(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         ctor: &TypeConstructor,
         sum_type: &SumType,
         output: &mut Doc
@@ -263,13 +318,16 @@ pub trait CodeGenerator {
     fn visit_sum_type_scalar_variant(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         variant_name: &str,
         sum_type: &SumType,
         output: &mut Doc
     ) -> Result<(), Box<dyn Error>>;
 
     /// 实现此方法并将其他所有 `visit` 方法留空，可以完全地自定义代码生成器的行为
+    ///
+    /// 注意：`typedefs` 中的注解不包含继承自全局注解的部分，
+    /// 请使用 [`ResolveContext::effective_attr`] 计算实际生效的注解
     fn visit_all_typedefs(
         &mut self,
         _ctx: &ResolveContext,
@@ -279,6 +337,14 @@ pub trait CodeGenerator {
         Ok(())
     }
 
+    /// 设置代码生成器的选项
+    ///
+    /// 选项来自项目配置文件中的 `[generators.<语言标识符>]` 表，在生成代码之前设置。
+    /// 代码生成器应该对不认识的选项返回 `Err`，默认的实现不接受任何选项
+    fn set_option(&mut self, name: &str, _value: &str) -> Result<(), Box<dyn Error>> {
+        Err(format!("不支持选项 {}", name).into())
+    }
+
     /// 在开始生成代码时的行为
     ///
     /// 用户可以通过实现此方法来向生成的代码中添加一些头部信息，
@@ -300,207 +366,257 @@ pub fn codegen(
     tyde: &[TypeDef],
     ctx: &ResolveContext,
     codegen: &mut dyn CodeGenerator
-) -> Result<Doc, Box<dyn Error>> {
-    if let Err(e) = check_reserved_idents(ctx, codegen, namespace, tyde) {
-        error!("{e}");
-        return Err(e);
-    }
+) -> Result<Doc, Diagnostics> {
+    let mut diags = Diagnostics::new();
+    check_reserved_idents(ctx, codegen, namespace, tyde, &mut diags);
 
     let mut output = Doc::new(0);
-    codegen.pre_visit(ctx, &mut output)?;
+    if let Err(err) = codegen.pre_visit(ctx, &mut output) {
+        diags.push(codegen_error(codegen, None, "生成文件头部", err));
+    }
 
+    let mut entered_namespace = None;
     if let Some(namespace) = namespace {
-        codegen.visit_namespace_begin(namespace, &mut output)
-            .map_err(|err| {
-                error!(
-                    "{}: 进入命名空间 {} 时遇到错误: {}",
-                    codegen.generator_name(),
-                    namespace,
-                    err
-                );
+        match codegen.visit_namespace_begin(namespace, &mut output) {
+            Ok(()) => entered_namespace = Some(namespace),
+            Err(err) => diags.push(codegen_error(
+                codegen,
+                None,
+                &format!("进入命名空间 {}", namespace),
                 err
-            })?;
+            ))
+        }
     }
 
-    codegen.visit_all_typedefs(ctx, tyde, &mut output)
-        .map_err(|err| {
-            error!(
-                "{}: 生成文件 {} 时遇到错误: {}",
-                codegen.generator_name(),
-                tyde[0].file,
-                err
-            );
-            err
-        })?;
+    if let Err(err) = codegen.visit_all_typedefs(ctx, tyde, &mut output) {
+        diags.push(codegen_error(codegen, None, "生成代码", err));
+    }
 
     for d in tyde {
+        let attr = ctx.effective_attr(&d.attr);
         match &d.inner {
             TypeDefInner::AliasType(name, aliased) => {
-                codegen.visit_type_alias(ctx, &d.attr, &name, &aliased, &mut output)
-                    .map_err(|err| {
-                        error!(
-                            "{}: 生成文件 {} 中的类型别名 {} 时遇到错误: {}",
-                            codegen.generator_name(),
-                            d.file,
-                            name,
-                            err
-                        );
+                if let Err(err) = codegen.visit_type_alias(ctx, &attr, name, aliased, &mut output) {
+                    diags.push(codegen_error(
+                        codegen,
+                        Some(&d.span),
+                        &format!("生成类型别名 {}", name),
                         err
-                    })?
+                    ));
+                }
             },
             TypeDefInner::SimpleType(simple_type) => {
-                codegen.visit_simple_type(ctx, &d.attr, &simple_type, &mut output)
-                    .map_err(|err| {
-                        error!(
-                            "{}: 生成文件 {} 中的简单类型 {} 时遇到错误: {}",
-                            codegen.generator_name(),
-                            d.file,
-                            simple_type.name,
-                            err
-                        );
+                if let Err(err) = codegen.visit_simple_type(ctx, &attr, simple_type, &mut output) {
+                    diags.push(codegen_error(
+                        codegen,
+                        Some(&d.span),
+                        &format!("生成简单类型 {}", simple_type.name),
                         err
-                    })?
+                    ));
+                }
             },
             TypeDefInner::SumType(sum_type) => {
-                codegen.visit_sum_type(ctx, &d.attr, &sum_type, &mut output)
-                    .map_err(|err| {
-                        error!(
-                            "{}: 生成文件 {} 中的和类型 {} 时遇到错误: {}",
-                            codegen.generator_name(),
-                            d.file,
-                            sum_type.name,
-                            err
-                        );
+                if let Err(err) = codegen.visit_sum_type(ctx, &attr, sum_type, &mut output) {
+                    diags.push(codegen_error(
+                        codegen,
+                        Some(&d.span),
+                        &format!("生成和类型 {}", sum_type.name),
                         err
-                    })?;
+                    ));
+                }
 
-                for (attr, variant) in &sum_type.scalar_variants {
-                    codegen.visit_sum_type_scalar_variant(
+                for (attr, variant, span) in &sum_type.scalar_variants {
+                    if let Err(err) = codegen.visit_sum_type_scalar_variant(
                         ctx,
                         attr,
                         variant,
-                        &sum_type,
+                        sum_type,
                         &mut output
-                    ).map_err(|err| {
-                        error!(
-                            "{}: 生成文件 {} 中的和类型 {} 的变体 {} 时遇到错误: {}",
-                            codegen.generator_name(),
-                            d.file,
-                            sum_type.name,
-                            variant,
+                    ) {
+                        diags.push(codegen_error(
+                            codegen,
+                            Some(span),
+                            &format!("生成和类型 {} 的变体 {}", sum_type.name, variant),
                             err
-                        );
-                        err
-                    })?;
+                        ));
+                    }
                 }
 
                 for (attr, ctor) in &sum_type.ctors {
-                    codegen.visit_sum_type_ctor(
+                    if let Err(err) = codegen.visit_sum_type_ctor(
                         ctx,
                         attr,
                         ctor,
-                        &sum_type,
+                        sum_type,
                         &mut output
-                    ).map_err(|err| {
-                        error!(
-                            "{}: 生成文件 {} 中的和类型 {} 的构造函数 {} 时遇到错误: {}",
-                            codegen.generator_name(),
-                            d.file,
-                            sum_type.name,
-                            ctor.name,
+                    ) {
+                        diags.push(codegen_error(
+                            codegen,
+                            Some(&ctor.span),
+                            &format!("生成和类型 {} 的构造函数 {}", sum_type.name, ctor.name),
                             err
-                        );
-                        err
-                    })?;
+                        ));
+                    }
                 }
             }
         }
     }
 
-    if let Some(namespace) = namespace {
-        codegen.visit_namespace_end(namespace, &mut output)
-            .map_err(|err| {
-                error!(
-                    "{}: 离开命名空间 {} 时遇到错误: {}",
-                    codegen.generator_name(),
-                    namespace,
-                    err
-                );
+    if let Some(namespace) = entered_namespace {
+        if let Err(err) = codegen.visit_namespace_end(namespace, &mut output) {
+            diags.push(codegen_error(
+                codegen,
+                None,
+                &format!("离开命名空间 {}", namespace),
                 err
-            })?;
+            ));
+        }
+    }
+
+    if !diags.is_empty() {
+        return Err(diags);
     }
 
     Ok(output)
 }
 
+/// 生成带有类型参数的类型名称，例如 `Result<T, E>`，没有类型参数时只返回类型名称
+///
+/// Rust 和 TypeScript 的泛型语法相同，代码生成器可以共用这个函数
+pub fn generic_type_name<S: AsRef<str>>(name: &str, type_params: &[S]) -> String {
+    if type_params.is_empty() {
+        return name.to_string();
+    }
+
+    let type_params = type_params
+        .iter()
+        .map(AsRef::as_ref)
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{}<{}>", name, type_params)
+}
+
+/// 将代码生成器返回的错误包装为诊断信息
+///
+/// 如果代码生成器返回的错误本身就是带有位置的 [`Diagnostic`]（例如注解格式错误），
+/// 则优先使用错误自带的位置
+fn codegen_error(
+    codegen: &dyn CodeGenerator,
+    span: Option<&Span>,
+    action: &str,
+    err: Box<dyn Error>
+) -> Diagnostic {
+    if let Some(diag) = err.downcast_ref::<Diagnostic>() {
+        if diag.span.is_some() {
+            return Diagnostic {
+                span: diag.span.clone(),
+                severity: Severity::Error,
+                message: format!(
+                    "{}: {} 时遇到错误: {}",
+                    codegen.generator_name(),
+                    action,
+                    diag.message
+                )
+            };
+        }
+    }
+
+    Diagnostic {
+        span: span.cloned(),
+        severity: Severity::Error,
+        message: format!(
+            "{}: {} 时遇到错误: {}",
+            codegen.generator_name(),
+            action,
+            err
+        )
+    }
+}
+
 fn check_reserved_idents(
     ctx: &ResolveContext,
     codegen: &dyn CodeGenerator,
     namespace: Option<&str>,
-    tyde: &[TypeDef]
-) -> Result<(), Box<dyn Error>> {
+    tyde: &[TypeDef],
+    diags: &mut Diagnostics
+) {
     let reserved_idents = codegen.reserved_idents()
-        .into_iter()
+        .iter()
         .map(Deref::deref)
         .collect::<HashSet<_>>();
 
+    let unescapable = |context: IdentContext, ident: &str| {
+        reserved_idents.contains(ident) && codegen.escape_ident(context, ident).is_none()
+    };
+
     if let Some(namespace) = namespace {
-        if reserved_idents.contains(namespace) {
-            return Err(format!(
-                "{}: 生成器报告命名空间名称 {} 与保留标识符冲突",
+        if unescapable(IdentContext::Namespace, namespace) {
+            diags.push(Diagnostic::without_span(format!(
+                "{}: 生成器报告命名空间名称 {} 与无法转义的保留标识符冲突",
                 codegen.generator_name(),
                 namespace
-            ).into());
+            )));
         }
     }
 
-    for (ty_name, (exist_in_file, _, is_inline)) in ctx.known_types.iter() {
-        if reserved_idents.contains(ty_name.as_str()) && !is_inline {
-            return Err(format!(
-                "{}: 生成器报告文件 {} 中的非内联类型 {} 与保留标识符冲突",
+    for (ty_name, known_type) in ctx.known_types.iter() {
+        if unescapable(IdentContext::TypeName, ty_name) && !known_type.is_inline {
+            diags.push(Diagnostic::new(known_type.span.clone(), format!(
+                "{}: 生成器报告非内联类型 {} 与无法转义的保留标识符冲突",
                 codegen.generator_name(),
-                exist_in_file,
                 ty_name
-            ).into());
+            )));
         }
     }
 
     for tyde in tyde {
+        let type_rule = rename_rule(&ctx.effective_attr(&tyde.attr));
         match &tyde.inner {
             TypeDefInner::SimpleType(simple_type) => {
-                for (_, _, _, field_name) in &simple_type.fields {
-                    if reserved_idents.contains(field_name.as_str()) {
-                        return Err(format!(
-                            "{}: 生成器报告文件 {} 中的简单类型 {} 的字段 {} 与保留标识符冲突",
+                for field in &simple_type.fields {
+                    let field_name = codegen.field_name(type_rule, field);
+                    if unescapable(IdentContext::FieldName, &field_name) {
+                        diags.push(Diagnostic::new(field.span.clone(), format!(
+                            "{}: 生成器报告简单类型 {} 的字段 {} 与无法转义的保留标识符冲突",
                             codegen.generator_name(),
-                            tyde.file,
                             simple_type.name,
                             field_name
-                        ).into());
+                        )));
                     }
                 }
             },
             TypeDefInner::SumType(sum_type) => {
-                for (_, ctor) in &sum_type.ctors {
-                    for (_, _, _, field_name) in &ctor.fields {
-                        if reserved_idents.contains(field_name.as_str()) {
-                            return Err(format!(
-                                "{}: 生成器报告文件 {} 中的和类型 {} 的构造函数 {} 的字段 {} 与保留标识符冲突",
+                for (ctor_attr, ctor) in &sum_type.ctors {
+                    let ctor_rule = rename_rule(ctor_attr).or(type_rule);
+                    for field in &ctor.fields {
+                        let field_name = codegen.field_name(ctor_rule, field);
+                        if unescapable(IdentContext::FieldName, &field_name) {
+                            diags.push(Diagnostic::new(field.span.clone(), format!(
+                                "{}: 生成器报告和类型 {} 的构造函数 {} 的字段 {} 与无法转义的保留标识符冲突",
                                 codegen.generator_name(),
-                                tyde.file,
                                 sum_type.name,
                                 ctor.name,
                                 field_name
-                            ).into());
+                            )));
                         }
                     }
                 }
+
+                for field in &sum_type.common_fields {
+                    let field_name = codegen.field_name(type_rule, field);
+                    if unescapable(IdentContext::FieldName, &field_name) {
+                        diags.push(Diagnostic::new(field.span.clone(), format!(
+                            "{}: 生成器报告和类型 {} 的公共字段 {} 与无法转义的保留标识符冲突",
+                            codegen.generator_name(),
+                            sum_type.name,
+                            field_name
+                        )));
+                    }
+                }
             },
             _ => {}
         }
     }
-
-    Ok(())
 }
 
 /// 代码生成器工厂，用于创建代码生成器
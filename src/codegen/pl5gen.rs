@@ -13,7 +13,8 @@ use crate::{
     parser::hir::{
         SumType,
         RSDLType,
-        AttrItem,
+        Attr,
+        Optionality,
         TypeConstructor,
         check_boxed,
         check_ident_attr,
@@ -30,9 +31,9 @@ impl PL5Generator {
     fn type_to_string(&self, ctx: &ResolveContext, ty: &RSDLType) -> Option<String> {
         match ty {
             RSDLType::Identifier(ident) => {
-                if let Some((_, rsdl_type, is_inline)) = ctx.known_types.get(ident) {
-                    if *is_inline {
-                        let Some(rsdl_type) = rsdl_type else { return None; };
+                if let Some(known_type) = ctx.known_types.get(ident) {
+                    if known_type.is_inline {
+                        let Some(rsdl_type) = &known_type.aliased else { return None; };
                         self.type_to_string(ctx, rsdl_type)
                     } else {
                         Some(ident.to_string())
@@ -52,7 +53,10 @@ impl PL5Generator {
                 let inner = self.type_to_string(ctx, &inner)?;
                 Some("list".to_string())
             },
-            RSDLType::Record(inner) => {
+            RSDLType::Map(..) | RSDLType::Set(..) => {
+                None
+            },
+            RSDLType::Array(..) | RSDLType::Tuple(..) | RSDLType::Generic(..) => {
                 None
             }
         }
@@ -60,7 +64,7 @@ impl PL5Generator {
 
     fn gen_doc(
         &self,
-        attr_list: &[AttrItem],
+        attr_list: &[Attr],
         doc_attr_name: &str,
         output: &mut Doc
     ) -> Result<(), Box<dyn Error>> {
@@ -79,8 +83,8 @@ impl PL5Generator {
     fn imp_visit_simple_type(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
-        _sum_type_attr: Option<&[AttrItem]>,
+        attr: &[Attr],
+        _sum_type_attr: Option<&[Attr]>,
         type_ctor: &TypeConstructor,
         output: &mut Doc,
 
@@ -90,18 +94,18 @@ impl PL5Generator {
 
         self.gen_doc(attr, doc_attr_name, output)?;
 
-        for (attr, _, _, name) in &type_ctor.fields {
+        for field in &type_ctor.fields {
             let mut field_doc = Box::new(Doc::new(4));
-            self.gen_doc(attr, "doc", &mut field_doc)?;
+            self.gen_doc(&field.attr, "doc", &mut field_doc)?;
             if !field_doc.items.is_empty() {
-                output.push_string(format!("- {}", self.lispify(name)));
+                output.push_string(format!("- {}", self.lispify(&field.name)));
                 output.push_doc(field_doc);
             }
         }
 
         let mut maker_args = String::new();
-        for (_, _, _, name) in &type_ctor.fields {
-            maker_args.push_str(&self.lispify(name));
+        for field in &type_ctor.fields {
+            maker_args.push_str(&self.lispify(&field.name));
             maker_args.push(' ');
         }
         if !maker_args.is_empty() {
@@ -111,16 +115,16 @@ impl PL5Generator {
         output.push_string(format!("(define (make-{} {})", ctor_name_lisp, maker_args));
         let mut maker_body = Box::new(Doc::new(4));
 
-        for (_, optional, ty, name) in &type_ctor.fields {
-            let type_name = self.type_to_string(ctx, ty).ok_or_else(|| {
-                format!("无法将类型 {:?} 转换为 PL5 类型", ty)
+        for field in &type_ctor.fields {
+            let type_name = self.type_to_string(ctx, &field.ty).ok_or_else(|| {
+                format!("无法将类型 {:?} 转换为 PL5 类型", field.ty)
             })?;
             if type_name.is_empty() {
                 continue;
             }
 
-            let name_lisp = self.lispify(name);
-            if *optional {
+            let name_lisp = self.lispify(&field.name);
+            if field.optionality != Optionality::Required {
                 maker_body.push_string(format!(
                     "(assert (or (null? {name_lisp}) ({type_name}? {name_lisp})) \"字段 {name_lisp} 的类型必须是 {type_name} 或者 null\")",
                 ));
@@ -133,8 +137,8 @@ impl PL5Generator {
 
         maker_body.push_string(format!("(struct 'k '{ctor_name_lisp}"));
         let mut struct_fields = Box::new(Doc::new(8));
-        for (_, _, _, name) in &type_ctor.fields {
-            let name_dashed = self.lispify(name);
+        for field in &type_ctor.fields {
+            let name_dashed = self.lispify(&field.name);
             struct_fields.push_string(format!(
                 "'{name_dashed} {name_dashed}",
             ));
@@ -187,7 +191,7 @@ impl CodeGenerator for PL5Generator {
     fn visit_type_alias(
         &mut self,
         _ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         _alias_name: &str,
         _target_type: &RSDLType,
         _output: &mut Doc
@@ -202,7 +206,7 @@ impl CodeGenerator for PL5Generator {
     fn visit_simple_type(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         type_ctor: &TypeConstructor,
         output: &mut Doc
     ) -> Result<(), Box<dyn Error>> {
@@ -219,7 +223,7 @@ impl CodeGenerator for PL5Generator {
     fn visit_sum_type(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         sum_type: &SumType,
         output: &mut Doc
     ) -> Result<(), Box<dyn Error>> {
@@ -229,7 +233,7 @@ impl CodeGenerator for PL5Generator {
     fn visit_sum_type_ctor(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         ctor: &TypeConstructor,
         sum_type: &SumType,
         output: &mut Doc
@@ -240,7 +244,7 @@ impl CodeGenerator for PL5Generator {
     fn visit_sum_type_scalar_variant(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         variant_name: &str,
         sum_type: &SumType,
         output: &mut Doc
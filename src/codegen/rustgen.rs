@@ -1,84 +1,156 @@
 //! Rust 代码生成器
 
+use std::collections::HashSet;
 use std::error::Error;
 
 use smallvec::SmallVec;
 
 use crate::{
+    diagnostic::Span,
+    attr_spec::{AttrPosition, AttrShape, AttrSpec, ALL_POSITIONS},
     codegen::{
         CodeGenerator,
         CodeGeneratorFactory,
-        Doc
+        Doc,
+        IdentContext,
+        generic_type_name
     },
     parser::hir::{
         SumType,
         RSDLType,
+        Attr,
         AttrItem,
+        AttrQuery,
+        Field,
+        Literal,
+        Optionality,
         TypeConstructor,
+        TypeDef,
+        TypeDefInner,
         check_boxed,
-        check_ident_attr,
         check_inline,
         check_private,
-        extract_doc_strings
+        extract_doc_strings,
+        used_type_params
     },
-    min_resolv::ResolveContext
+    min_resolv::{MapKeyKind, ResolveContext, SumRepr, SumTagging},
+    naming::{rename_rule, target_name, wire_name, RenameRule}
 };
 
 /// Rust 代码生成器
-pub struct RustGenerator();
+#[derive(Debug, Default)]
+pub struct RustGenerator {
+    /// 通过 `header` 选项指定的，在生成的代码开头输出的内容
+    header: Vec<String>,
+    /// 通过 `collections = "btree"` 选项指定，使用 `BTreeMap` 和 `BTreeSet` 代替
+    /// `HashMap` 和 `HashSet`
+    btree_collections: bool,
+    /// 作为映射的键或者集合的元素使用的和类型，它们需要派生 `Hash` 和 `Eq`（或者 `Ord`）
+    key_enums: HashSet<String>
+}
 
 impl RustGenerator {
     fn type_to_string(&self, ctx: &ResolveContext, ty: &RSDLType) -> Option<String> {
         match ty {
             RSDLType::Identifier(ident) => {
-                if let Some((_, rsdl_type, is_inline)) = ctx.known_types.get(ident) {
-                    if *is_inline {
-                        let Some(rsdl_type) = rsdl_type else { return None; };
+                if let Some(known_type) = ctx.known_types.get(ident) {
+                    if known_type.is_inline {
+                        let Some(rsdl_type) = &known_type.aliased else { return None; };
                         self.type_to_string(ctx, rsdl_type)
                     } else {
-                        Some(ident.to_string())
+                        Some(self.type_ident(ident))
                     }
                 } else {
-                    None
+                    // 通过了检查的类型中，只有类型参数不在已知类型表中
+                    Some(ident.to_string())
                 }
             },
+            RSDLType::Generic(ident, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.type_to_string(ctx, arg))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(generic_type_name(&self.type_ident(ident), &args))
+            },
             RSDLType::Native(native) => {
-                if let Some(rust_name) = native.get(self.lang_ident()) {
-                    Some(rust_name.to_string())
-                } else {
-                    None
-                }
+                native.get(self.lang_ident()).map(|rust_name| rust_name.to_string())
             },
             RSDLType::List(inner) => {
-                let inner = self.type_to_string(ctx, &inner)?;
+                let inner = self.type_to_string(ctx, inner)?;
                 Some(format!("Vec<{}>", inner))
             },
-            RSDLType::Record(inner) => {
-                let inner = self.type_to_string(ctx, &inner)?;
-                Some(format!("HashMap<String, {}>", inner))
+            RSDLType::Array(inner, len) => {
+                let inner = self.type_to_string(ctx, inner)?;
+                Some(format!("[{}; {}]", inner, len))
+            },
+            RSDLType::Tuple(items) => {
+                let items = items
+                    .iter()
+                    .map(|item| self.type_to_string(ctx, item))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(format!("({})", items.join(", ")))
+            },
+            RSDLType::Map(key, value) => {
+                let key = self.type_to_string(ctx, key)?;
+                let value = self.type_to_string(ctx, value)?;
+                Some(format!(
+                    "::std::collections::{}<{}, {}>",
+                    if self.btree_collections { "BTreeMap" } else { "HashMap" },
+                    key,
+                    value
+                ))
+            },
+            RSDLType::Set(inner) => {
+                let inner = self.type_to_string(ctx, inner)?;
+                Some(format!(
+                    "::std::collections::{}<{}>",
+                    if self.btree_collections { "BTreeSet" } else { "HashSet" },
+                    inner
+                ))
             }
         }
     }
 
+    /// 记录类型中作为映射的键或者集合的元素使用的和类型
+    fn collect_key_enums(&mut self, ctx: &ResolveContext, ty: &RSDLType) {
+        match ty {
+            RSDLType::Map(key, value) => {
+                if let Some(MapKeyKind::Enum(name)) = ctx.map_key_kind(key) {
+                    self.key_enums.insert(name);
+                }
+                self.collect_key_enums(ctx, key);
+                self.collect_key_enums(ctx, value);
+            },
+            RSDLType::Set(inner) => {
+                if let Some(MapKeyKind::Enum(name)) = ctx.map_key_kind(inner) {
+                    self.key_enums.insert(name);
+                }
+                self.collect_key_enums(ctx, inner);
+            },
+            RSDLType::List(inner) | RSDLType::Array(inner, _) => self.collect_key_enums(ctx, inner),
+            RSDLType::Tuple(items) | RSDLType::Generic(_, items) => {
+                for item in items {
+                    self.collect_key_enums(ctx, item);
+                }
+            },
+            RSDLType::Identifier(_) | RSDLType::Native(_) => {}
+        }
+    }
+
+    /// 类型（以及和类型的变体）在生成的 Rust 代码中的名称，保留标识符已经被转义，例如 `r#mod`
+    fn type_ident(&self, name: &str) -> String {
+        self.target_ident(IdentContext::TypeName, name)
+    }
+
     fn gen_rust_derive(
         &self,
-        attr_list: &[AttrItem],
+        attr_list: &[Attr],
+        required: &[&str],
         output: &mut Doc
     ) -> Result<(), Box<dyn Error>> {
         let mut derived_names: SmallVec<[&str; 4]> = SmallVec::new();
-        for attr in attr_list {
-            if let AttrItem::CallAlike(fn_alike, param_alike) = attr {
-                if fn_alike == "rust_derive" {
-                    for param in param_alike {
-                        if let AttrItem::Identifier(ident) = param {
-                            derived_names.push(ident);
-                        } else {
-                            return Err("derive 属性的参数必须是标识符".into());
-                        }
-                    }
-                }
-            }
-        }
+        derived_names.extend(attr_list.ident_args("rust_derive")?);
+        derived_names.extend_from_slice(required);
 
         if self.check_rust_serde(attr_list) {
             derived_names.push("Serialize");
@@ -99,55 +171,89 @@ impl RustGenerator {
         Ok(())
     }
 
-    fn check_rust_skip(&self, attr_list: &[AttrItem]) -> bool {
-        check_ident_attr(attr_list, "rust_skip")
+    fn check_rust_skip(&self, attr_list: &[Attr]) -> bool {
+        attr_list.flag("rust_skip")
     }
 
-    fn check_rust_serde(&self, attr_list: &[AttrItem]) -> bool {
-        check_ident_attr(attr_list, "rust_serde")
+    fn check_rust_serde(&self, attr_list: &[Attr]) -> bool {
+        attr_list.flag("rust_serde")
     }
 
-    fn check_rust_rkyv(&self, attr_list: &[AttrItem]) -> bool {
-        check_ident_attr(attr_list, "rust_rkyv")
+    fn check_rust_rkyv(&self, attr_list: &[Attr]) -> bool {
+        attr_list.flag("rust_rkyv")
     }
 
+    fn check_rust_wrapper(&self, attr_list: &[Attr]) -> bool {
+        attr_list.flag("rust_wrapper")
+    }
+
+    /// 生成 `rust_attr` 等注解指定的 Rust attribute。如果 `sum_tagging` 不为空并且使用了 `rust_serde`，
+    /// 还会生成和类型的表示方式对应的 `#[serde(...)]`
     fn gen_rust_attr(
         &self,
-        attr_list: &[AttrItem],
+        attr_list: &[Attr],
         rust_attr_name: &str,
         output: &mut Doc,
-        ctx: &ResolveContext,
-        is_enum_context: bool
+        sum_tagging: Option<&SumTagging>
     ) -> Result<(), Box<dyn Error>> {
-        for attr in attr_list {
-            match attr {
-                AttrItem::CallAlike(fn_alike, param_alike) => {
-                    if fn_alike == rust_attr_name {
-                        for rust_attr in param_alike {
-                            output.push_string(format!(
-                                "#[{}]",
-                                self.gen_single_rust_attr(rust_attr)?
-                            ));
-                        }
-                    }
-                },
-                _ => {}
-            }
+        for rust_attr in attr_list.call_args(rust_attr_name)? {
+            output.push_string(format!(
+                "#[{}]",
+                self.gen_single_rust_attr(rust_attr)?
+            ));
+        }
+
+        let Some(tagging) = sum_tagging else { return Ok(()); };
+        if !self.check_rust_serde(attr_list) {
+            return Ok(());
         }
 
-        if is_enum_context && self.check_rust_serde(attr_list) {
-            output.push_string(format!("#[serde(tag = \"{}\")]", ctx.discriminant));
+        match tagging.repr {
+            SumRepr::Internal => {
+                output.push_string(format!("#[serde(tag = {:?})]", tagging.discriminant));
+            },
+            SumRepr::Adjacent => {
+                output.push_string(format!(
+                    "#[serde(tag = {:?}, content = {:?})]",
+                    tagging.discriminant,
+                    tagging.content
+                ));
+            },
+            SumRepr::External => {},
+            SumRepr::Untagged => {
+                output.push_str("#[serde(untagged)]");
+            }
         }
 
         Ok(())
     }
 
+    /// 为使用 `[tag_value(...)]` 指定了标签值的变体生成 `#[serde(rename = ...)]`
+    fn gen_serde_rename(&self, sum_tagging: Option<&SumTagging>, variant: &str, output: &mut Doc) {
+        if let Some(tag_value) = sum_tagging.and_then(|tagging| tagging.tag_values.get(variant)) {
+            output.push_string(format!("#[serde(rename = {:?})]", tag_value));
+        }
+    }
+
     fn gen_single_rust_attr(
         &self,
         attr: &AttrItem
     ) -> Result<String, Box<dyn Error>> {
         match attr {
             AttrItem::Identifier(ident) => Ok(ident.to_string()),
+            AttrItem::String(s) => Ok(format!("{:?}", s)),
+            AttrItem::Integer(i) => Ok(i.to_string()),
+            AttrItem::Float(f) => Ok(format!("{:?}", f)),
+            AttrItem::Bool(b) => Ok(b.to_string()),
+            AttrItem::List(items) => {
+                let items_str = items
+                    .iter()
+                    .map(|item| self.gen_single_rust_attr(item))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(", ");
+
+                Ok(format!("[{}]", items_str))
+            },
             AttrItem::CallAlike(fn_alike, param_alike) => {
                 let param_str = param_alike
                     .iter()
@@ -158,19 +264,14 @@ impl RustGenerator {
                 Ok(format!("{}({})", fn_alike, param_str))
             },
             AttrItem::Assignment(assignee, value) => {
-                match value.as_ref() {
-                    AttrItem::String(s) => Ok(format!("{} = \"{}\"", assignee, s)),
-                    AttrItem::Identifier(ident) => Ok(format!("{} = {}", assignee, ident)),
-                    _ => Err("属性赋值的值必须是字符串字面量或标识符".into())
-                }
-            },
-            _ => Err("属性必须是标识符、函数调用或赋值".into())
+                Ok(format!("{} = {}", assignee, self.gen_single_rust_attr(value)?))
+            }
         }
     }
 
     fn gen_doc(
         &self,
-        attr_list: &[AttrItem],
+        attr_list: &[Attr],
         doc_attr_name: &str,
         output: &mut Doc
     ) -> Result<(), Box<dyn Error>> {
@@ -186,12 +287,14 @@ impl RustGenerator {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn imp_visit_simple_type(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
-        sum_type_attr: Option<&[AttrItem]>,
+        attr: &[Attr],
+        sum_type_attr: Option<&[Attr]>,
         type_ctor: &TypeConstructor,
+        rules: &[Option<RenameRule>],
         output: &mut Doc,
 
         doc_attr_name: &str,
@@ -202,57 +305,387 @@ impl RustGenerator {
         }
 
         self.gen_doc(attr, doc_attr_name, output)?;
-        self.gen_rust_derive(attr, output)?;
+        self.gen_rust_derive(attr, &[], output)?;
         if let Some(sum_type_attr) = sum_type_attr {
-            self.gen_rust_derive(sum_type_attr, output)?;
+            self.gen_rust_derive(sum_type_attr, &[], output)?;
         }
-        self.gen_rust_attr(attr, rust_attr_name, output, ctx, false)?;
+        self.gen_rust_attr(attr, rust_attr_name, output, None)?;
         let private = check_private(attr);
+        let serde = self.check_rust_serde(attr)
+            || sum_type_attr.is_some_and(|sum_type_attr| self.check_rust_serde(sum_type_attr));
 
         output.push_string(format!(
             "{}struct {} {{",
             if private { "" } else { "pub " },
-            type_ctor.name
+            generic_type_name(&self.type_ident(&type_ctor.name), &type_ctor.type_params)
         ));
 
         let mut struct_fields = Box::new(Doc::new(4));
+        self.gen_fields(ctx, &type_ctor.name, &type_ctor.type_params, &type_ctor.fields, rules, serde, &mut struct_fields)?;
+        output.push_doc(struct_fields);
 
-        for (attr, optional, field_type, field_name) in &type_ctor.fields {
-            self.gen_doc(attr, "doc", &mut struct_fields)?;
-            self.gen_rust_attr(attr, "rust_attr", &mut struct_fields, ctx, false)?;
-            let field_private = check_private(attr);
-            let field_boxed = check_boxed(attr);
+        output.push_str("}");
+        output.push_empty_line();
 
-            let inner_type = if field_boxed {
-                format!("Box<{}>", self.type_to_string(ctx, field_type)
-                    .ok_or("RSDL native 类型缺少对应的 Rust 类型")?)
-            } else {
-                self.type_to_string(ctx, field_type)
-                    .ok_or("RSDL native 类型缺少对应的 Rust 类型")?
+        self.gen_default_impl(ctx, &type_ctor.name, &type_ctor.type_params, &type_ctor.fields, serde, true, output)?;
+
+        Ok(())
+    }
+
+    /// 生成结构体的字段，`rules` 是每个字段所在的类型（或者构造器）指定的命名风格，
+    /// 与 `fields` 一一对应
+    #[allow(clippy::too_many_arguments)]
+    fn gen_fields(
+        &self,
+        ctx: &ResolveContext,
+        struct_name: &str,
+        type_params: &[String],
+        fields: &[Field],
+        rules: &[Option<RenameRule>],
+        serde: bool,
+        output: &mut Doc
+    ) -> Result<(), Box<dyn Error>> {
+        // 泛型结构体的关联函数需要使用 turbofish 语法引用，例如 Spanned::<T>::default_x
+        let struct_name = self.type_ident(struct_name);
+        let struct_path = if type_params.is_empty() {
+            struct_name.to_string()
+        } else {
+            format!("{}::<{}>", struct_name, type_params.join(", "))
+        };
+
+        let mut idents = HashSet::new();
+        for (field, rule) in fields.iter().zip(rules) {
+            let ident = self.field_ident(field);
+            if !idents.insert(unraw(&ident).to_string()) {
+                return Err(format!("字段 {} 对应的 Rust 标识符 {} 与其他字段重复", field.name, ident).into());
+            }
+
+            self.gen_doc(&field.attr, "doc", output)?;
+            self.gen_rust_attr(&field.attr, "rust_attr", output, None)?;
+            if serde {
+                self.gen_field_serde_attr(&struct_path, field, unraw(&ident), &wire_name(*rule, field), output);
+            }
+            let field_private = check_private(&field.attr);
+
+            output.push_string(format!(
+                "{}{}: {},",
+                if field_private { "" } else { "pub " },
+                ident,
+                self.field_type_to_string(ctx, field)?
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// 字段在生成的 Rust 代码中的标识符，保留标识符已经被转义，例如 `r#fn`
+    fn field_ident(&self, field: &Field) -> String {
+        self.target_ident(IdentContext::FieldName, &self.field_name(None, field))
+    }
+
+    fn field_type_to_string(&self, ctx: &ResolveContext, field: &Field) -> Result<String, Box<dyn Error>> {
+        let mut ty = self.type_to_string(ctx, &field.ty)
+            .ok_or("RSDL native 类型缺少对应的 Rust 类型")?;
+
+        if check_boxed(&field.attr) {
+            ty = format!("Box<{}>", ty);
+        }
+
+        Ok(match field.optionality {
+            Optionality::Required => ty,
+            Optionality::Optional | Optionality::Nullable => format!("Option<{}>", ty),
+            Optionality::OptionalNullable => format!("Option<Option<{}>>", ty)
+        })
+    }
+
+    /// 根据字段的线上名称、可选性和默认值生成 `#[serde(...)]`
+    ///
+    /// - Rust 标识符 `ident`（不带 `r#` 前缀）与线上名称 `wire_name` 不同时，使用 `rename` 保持线上名称不变
+    /// - 可以缺失的字段在反序列化时使用默认值，为 `None` 时不会被序列化
+    /// - 可以为 `null` 的字段为 `None` 时被序列化为 `null`
+    /// - 既可以缺失又可以为 `null` 的字段被表示为 `Option<Option<T>>`，缺失时为 `None`，
+    ///   为 `null` 时为 `Some(None)`
+    fn gen_field_serde_attr(
+        &self,
+        struct_path: &str,
+        field: &Field,
+        ident: &str,
+        wire_name: &str,
+        output: &mut Doc
+    ) {
+        let mut serde_args = Vec::new();
+        if ident != wire_name {
+            serde_args.push(format!("rename = {:?}", wire_name));
+        }
+
+        if field.default.is_some() {
+            serde_args.push(format!("default = \"{}::default_{}\"", struct_path, ident));
+        } else if field.optionality.is_optional() {
+            serde_args.push("default".to_string());
+        }
+
+        if field.optionality.is_optional() {
+            serde_args.push("skip_serializing_if = \"Option::is_none\"".to_string());
+        }
+
+        if field.optionality == Optionality::OptionalNullable {
+            serde_args.push(format!("deserialize_with = \"{}::deserialize_{}\"", struct_path, ident));
+        }
+
+        if !serde_args.is_empty() {
+            output.push_string(format!("#[serde({})]", serde_args.join(", ")));
+        }
+    }
+
+    /// 为带有默认值的字段生成返回默认值的关联函数，供 `#[serde(default = ...)]` 使用。
+    /// 如果 `serde` 为真，还会为既可以缺失又可以为 `null` 的字段生成反序列化函数，
+    /// 供 `#[serde(deserialize_with = ...)]` 使用。
+    /// 如果 `impl_default` 为真，并且每个字段都有默认值或者其类型实现了 `Default`
+    /// （参见 [`RustGenerator::type_has_default`]），还会生成 `Default` 实现，
+    /// 没有默认值的字段使用 `Default::default()`
+    #[allow(clippy::too_many_arguments)]
+    fn gen_default_impl(
+        &self,
+        ctx: &ResolveContext,
+        struct_name: &str,
+        type_params: &[String],
+        fields: &[Field],
+        serde: bool,
+        impl_default: bool,
+        output: &mut Doc
+    ) -> Result<(), Box<dyn Error>> {
+        let has_default = fields.iter().any(|field| field.default.is_some());
+        let has_deserialize_fn = serde && fields
+            .iter()
+            .any(|field| field.optionality == Optionality::OptionalNullable);
+        if !has_default && !has_deserialize_fn {
+            return Ok(());
+        }
+
+        let struct_name = &self.type_ident(struct_name);
+        output.push_string(format!(
+            "impl{} {} {{",
+            generic_type_name("", type_params),
+            generic_type_name(struct_name, type_params)
+        ));
+        let mut field_fns = Box::new(Doc::new(4));
+        for field in fields {
+            let Some(default) = &field.default else { continue; };
+
+            let mut value = self.literal_to_string(ctx, &field.ty, &default.value)?;
+            if check_boxed(&field.attr) {
+                value = format!("Box::new({})", value);
+            }
+            value = match field.optionality {
+                Optionality::Required => value,
+                Optionality::Optional | Optionality::Nullable => format!("Some({})", value),
+                Optionality::OptionalNullable => format!("Some(Some({}))", value)
             };
 
-            if *optional {
-                struct_fields.push_string(format!(
-                    "{}{}: Option<{}>,",
-                    if field_private { "" } else { "pub " },
-                    field_name,
-                    inner_type
+            field_fns.push_string(format!(
+                "fn default_{}() -> {} {{",
+                unraw(&self.field_ident(field)),
+                self.field_type_to_string(ctx, field)?
+            ));
+            let mut body = Box::new(Doc::new(4));
+            body.push_string(value);
+            field_fns.push_doc(body);
+            field_fns.push_str("}");
+            field_fns.push_empty_line();
+        }
+
+        if has_deserialize_fn {
+            for field in fields {
+                if field.optionality != Optionality::OptionalNullable {
+                    continue;
+                }
+
+                // 字段存在时（包括为 null 时）才会调用此函数，缺失的字段由 default 处理。
+                // 用户可能定义了名为 Result 等的类型，因此这里使用完整的路径
+                let field_type = self.field_type_to_string(ctx, field)?;
+                field_fns.push_string(format!(
+                    "fn deserialize_{}<'de, D>(deserializer: D) -> ::std::result::Result<{}, D::Error>",
+                    unraw(&self.field_ident(field)),
+                    field_type
                 ));
-            } else {
-                struct_fields.push_string(format!(
-                    "{}{}: {},",
-                    if field_private { "" } else { "pub " },
-                    field_name,
-                    inner_type
+                field_fns.push_str("where");
+                let mut bounds = Box::new(Doc::new(4));
+                bounds.push_str("D: ::serde::Deserializer<'de>,");
+                // 去掉外层的 Option，得到 Option<T>
+                bounds.push_string(format!(
+                    "{}: ::serde::Deserialize<'de>",
+                    &field_type["Option<".len()..field_type.len() - 1]
                 ));
+                field_fns.push_doc(bounds);
+                field_fns.push_str("{");
+                let mut body = Box::new(Doc::new(4));
+                body.push_str("::serde::Deserialize::deserialize(deserializer).map(::std::option::Option::Some)");
+                field_fns.push_doc(body);
+                field_fns.push_str("}");
+                field_fns.push_empty_line();
+            }
+        }
+        output.push_doc(field_fns);
+        output.push_str("}");
+        output.push_empty_line();
+
+        if !impl_default || !has_default {
+            return Ok(());
+        }
+
+        let all_defaultable = fields.iter().all(|field| {
+            field.default.is_some()
+                || field.optionality != Optionality::Required
+                || self.type_has_default(ctx, &field.ty)
+        });
+        if !all_defaultable {
+            return Ok(());
+        }
+
+        let default_bounds = type_params
+            .iter()
+            .map(|param| format!("{}: Default", param))
+            .collect::<Vec<_>>();
+        output.push_string(format!(
+            "impl{} Default for {} {{",
+            generic_type_name("", &default_bounds),
+            generic_type_name(struct_name, type_params)
+        ));
+        let mut default_fn = Box::new(Doc::new(4));
+        default_fn.push_str("fn default() -> Self {");
+        let mut body = Box::new(Doc::new(4));
+        body.push_str("Self {");
+        let mut field_values = Box::new(Doc::new(4));
+        for field in fields {
+            let ident = self.field_ident(field);
+            if field.default.is_some() {
+                field_values.push_string(format!("{}: Self::default_{}(),", ident, unraw(&ident)));
+            } else {
+                field_values.push_string(format!("{}: Default::default(),", ident));
+            }
+        }
+        body.push_doc(field_values);
+        body.push_str("}");
+        default_fn.push_doc(body);
+        default_fn.push_str("}");
+        output.push_doc(default_fn);
+        output.push_str("}");
+        output.push_empty_line();
+
+        Ok(())
+    }
+
+    /// 判断生成的 Rust 类型是否一定实现了 `Default`：基本类型、列表、映射、集合，
+    /// 以及由它们组成的元组和数组。用户定义的类型不一定实现了 `Default`
+    fn type_has_default(&self, ctx: &ResolveContext, ty: &RSDLType) -> bool {
+        if ctx.builtin_type(ty).is_some() {
+            return true;
+        }
+
+        match ctx.resolve_alias(ty) {
+            RSDLType::List(_) | RSDLType::Map(..) | RSDLType::Set(_) => true,
+            // 标准库只为不超过 12 个元素的元组和不超过 32 个元素的数组实现了 Default
+            RSDLType::Tuple(items) => items.len() <= 12 && items.iter().all(|item| self.type_has_default(ctx, item)),
+            RSDLType::Array(inner, len) => *len <= 32 && self.type_has_default(ctx, inner),
+            _ => false
+        }
+    }
+
+    fn literal_to_string(
+        &self,
+        ctx: &ResolveContext,
+        ty: &RSDLType,
+        literal: &Literal
+    ) -> Result<String, Box<dyn Error>> {
+        let type_name = self.type_to_string(ctx, ty)
+            .ok_or("RSDL native 类型缺少对应的 Rust 类型")?;
+
+        Ok(match literal {
+            // 根据 RSDL 类型而不是 Rust 类型名称判断，这样 float 的别名也能得到浮点数
+            Literal::Integer(i) if ctx.builtin_type(ty) == Some("float") => format!("{}.0", i),
+            Literal::Integer(i) => i.to_string(),
+            Literal::Float(f) => format!("{:?}", f),
+            Literal::Bool(b) => b.to_string(),
+            Literal::String(s) => format!("{:?}.into()", s),
+            Literal::Variant(variant) => format!("{}::{}", type_name, self.type_ident(variant)),
+            Literal::List(items) => match ctx.resolve_alias(ty) {
+                RSDLType::List(inner) => format!("vec![{}]", self.literals_to_string(ctx, inner, items)?),
+                RSDLType::Array(inner, _) => format!("[{}]", self.literals_to_string(ctx, inner, items)?),
+                RSDLType::Tuple(types) => {
+                    let items = types
+                        .iter()
+                        .zip(items)
+                        .map(|(ty, item)| self.literal_to_string(ctx, ty, item))
+                        .collect::<Result<Vec<_>, _>>()?
+                        .join(", ");
+
+                    format!("({})", items)
+                },
+                _ => return Err("列表默认值只能用于列表、数组或者元组类型".into())
             }
+        })
+    }
+
+    fn literals_to_string(
+        &self,
+        ctx: &ResolveContext,
+        ty: &RSDLType,
+        items: &[Literal]
+    ) -> Result<String, Box<dyn Error>> {
+        Ok(items
+            .iter()
+            .map(|item| self.literal_to_string(ctx, ty, item))
+            .collect::<Result<Vec<_>, _>>()?
+            .join(", "))
+    }
+
+    /// 为带有公共字段的和类型生成包装结构体，公共字段放在包装结构体中，
+    /// 和类型本身对应的 enum 则放在包装结构体的 `kind` 字段中
+    fn gen_wrapper_struct(
+        &self,
+        ctx: &ResolveContext,
+        attr: &[Attr],
+        sum_type: &SumType,
+        enum_name: &str,
+        output: &mut Doc
+    ) -> Result<(), Box<dyn Error>> {
+        if sum_type.common_fields.iter().any(|field| unraw(&self.field_ident(field)) == "kind") {
+            return Err("使用 rust_wrapper 时，公共字段不能命名为 kind".into());
+        }
+        if !used_type_params(&sum_type.type_params, sum_type.common_fields.iter()).is_empty() {
+            return Err("使用 rust_wrapper 时，公共字段不能使用类型参数".into());
         }
 
+        self.gen_doc(attr, "doc", output)?;
+        self.gen_rust_derive(attr, &[], output)?;
+        let private = check_private(attr);
+
+        output.push_string(format!(
+            "{}struct {} {{",
+            if private { "" } else { "pub " },
+            generic_type_name(&self.type_ident(&sum_type.name), &sum_type.type_params)
+        ));
+
+        let serde = self.check_rust_serde(attr);
+        let rules = vec![rename_rule(attr); sum_type.common_fields.len()];
+        let mut struct_fields = Box::new(Doc::new(4));
+        self.gen_fields(ctx, &sum_type.name, &sum_type.type_params, &sum_type.common_fields, &rules, serde, &mut struct_fields)?;
+        if serde {
+            struct_fields.push_str("#[serde(flatten)]");
+        }
+        struct_fields.push_string(format!(
+            "{}kind: {},",
+            if private { "" } else { "pub " },
+            generic_type_name(&self.type_ident(enum_name), &sum_type.type_params)
+        ));
         output.push_doc(struct_fields);
 
         output.push_str("}");
         output.push_empty_line();
 
+        // kind 字段没有默认值，因此包装结构体不实现 Default
+        self.gen_default_impl(ctx, &sum_type.name, &sum_type.type_params, &sum_type.common_fields, serde, false, output)?;
+
         Ok(())
     }
 }
@@ -321,12 +754,49 @@ impl CodeGenerator for RustGenerator {
         ]
     }
 
+    /// 保留标识符被转义为原始标识符 `r#xxx`。`self`、`Self`、`super` 和 `crate`
+    /// 不能作为原始标识符，作为字段名称时被转义为 `xxx_`，启用了 serde 的类型会通过
+    /// `#[serde(rename)]` 保持线上名称不变；作为类型名称和命名空间名称时无法转义
+    fn escape_ident(&self, context: IdentContext, ident: &str) -> Option<String> {
+        let raw_forbidden = matches!(ident, "self" | "Self" | "super" | "crate");
+        match context {
+            IdentContext::FieldName if raw_forbidden => Some(format!("{}_", ident)),
+            _ if raw_forbidden => None,
+            _ => Some(format!("r#{}", ident))
+        }
+    }
+
+    fn known_attrs(&self) -> &[AttrSpec] {
+        use AttrPosition::*;
+
+        const KNOWN_ATTRS: &[AttrSpec] = &[
+            AttrSpec::new("rust_derive", &[Type], AttrShape::Call),
+            AttrSpec::new("rust_attr", ALL_POSITIONS, AttrShape::Call),
+            AttrSpec::new("rust_attr_ctor", &[Ctor], AttrShape::Call),
+            AttrSpec::new("rust_serde", &[Type], AttrShape::Flag),
+            AttrSpec::new("rust_rkyv", &[Type], AttrShape::Flag),
+            AttrSpec::new("rust_skip", &[Type, Ctor], AttrShape::Flag),
+            AttrSpec::new("rust_wrapper", &[Type], AttrShape::Flag)
+        ];
+
+        KNOWN_ATTRS
+    }
+
+    /// Rust 字段总是使用 snake_case 标识符，除非使用 `[rename(rust = "...")]` 指定了其他名称。
+    /// 标识符与线上名称不同时，启用了 serde 的类型会通过 `#[serde(rename)]` 保持线上名称不变
+    fn field_name(&self, _rule: Option<RenameRule>, field: &Field) -> String {
+        match target_name(field, "rust") {
+            Some(name) => name.to_string(),
+            None => RenameRule::Snake.apply(&field.name)
+        }
+    }
+
     fn visit_namespace_begin(
         &mut self,
         namespace: &str,
         output: &mut Doc
     ) -> Result<(), Box<dyn Error>> {
-        output.push_string(format!("mod {} {{", namespace));
+        output.push_string(format!("mod {} {{", self.target_ident(IdentContext::Namespace, namespace)));
         Ok(())
     }
 
@@ -339,10 +809,67 @@ impl CodeGenerator for RustGenerator {
         Ok(())
     }
 
+    fn set_option(&mut self, name: &str, value: &str) -> Result<(), Box<dyn Error>> {
+        match name {
+            "header" => {
+                self.header = value.lines().map(ToString::to_string).collect();
+                Ok(())
+            },
+            "collections" => {
+                self.btree_collections = match value {
+                    "hash" => false,
+                    "btree" => true,
+                    _ => return Err(format!("选项 collections 的值只能是 hash 或者 btree，而不是 {}", value).into())
+                };
+                Ok(())
+            },
+            _ => Err(format!("不支持选项 {}", name).into())
+        }
+    }
+
+    fn pre_visit(
+        &mut self,
+        _ctx: &ResolveContext,
+        output: &mut Doc
+    ) -> Result<(), Box<dyn Error>> {
+        if !self.header.is_empty() {
+            for line in self.header.iter() {
+                output.push_string(line.clone());
+            }
+            output.push_empty_line();
+        }
+        Ok(())
+    }
+
+    fn visit_all_typedefs(
+        &mut self,
+        ctx: &ResolveContext,
+        typedefs: &[TypeDef],
+        _output: &mut Doc
+    ) -> Result<(), Box<dyn Error>> {
+        for typedef in typedefs {
+            match &typedef.inner {
+                TypeDefInner::AliasType(_, ty) => self.collect_key_enums(ctx, ty),
+                TypeDefInner::SimpleType(ctor) => {
+                    for field in &ctor.fields {
+                        self.collect_key_enums(ctx, &field.ty);
+                    }
+                },
+                TypeDefInner::SumType(sum_type) => {
+                    let ctor_fields = sum_type.ctors.iter().flat_map(|(_, ctor)| ctor.fields.iter());
+                    for field in ctor_fields.chain(sum_type.common_fields.iter()) {
+                        self.collect_key_enums(ctx, &field.ty);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn visit_type_alias(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         alias_name: &str,
         target_type: &RSDLType,
         output: &mut Doc
@@ -352,13 +879,13 @@ impl CodeGenerator for RustGenerator {
         }
 
         self.gen_doc(attr, "doc", output)?;
-        self.gen_rust_attr(attr, "rust_attr", output, ctx, false)?;
+        self.gen_rust_attr(attr, "rust_attr", output, None)?;
         let private = check_private(attr);
 
         output.push_string(format!(
             "{}type {} = {};",
             if private { "" } else {"pub "},
-            alias_name,
+            self.type_ident(alias_name),
             self.type_to_string(ctx, target_type)
                 .ok_or("RSDL native 类型缺少对应的 Rust 类型")?
         ));
@@ -370,15 +897,17 @@ impl CodeGenerator for RustGenerator {
     fn visit_simple_type(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         type_ctor: &TypeConstructor,
         output: &mut Doc
     ) -> Result<(), Box<dyn Error>> {
+        let rules = vec![rename_rule(attr); type_ctor.fields.len()];
         self.imp_visit_simple_type(
             ctx,
             attr,
             None,
             type_ctor,
+            &rules,
             output,
             "doc",
             "rust_attr"
@@ -388,7 +917,7 @@ impl CodeGenerator for RustGenerator {
     fn visit_sum_type_ctor(
         &mut self,
         _ctx: &ResolveContext,
-        _attr: &[AttrItem],
+        _attr: &[Attr],
         _ctor: &TypeConstructor,
         _sum_type: &SumType,
         _output: &mut Doc
@@ -399,7 +928,7 @@ impl CodeGenerator for RustGenerator {
     fn visit_sum_type_scalar_variant(
         &mut self,
         _ctx: &ResolveContext,
-        _attr: &[AttrItem],
+        _attr: &[Attr],
         _variant_name: &str,
         _sum_type: &SumType,
         _output: &mut Doc
@@ -410,7 +939,7 @@ impl CodeGenerator for RustGenerator {
     fn visit_sum_type(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         sum_type: &SumType,
         output: &mut Doc
     ) -> Result<(), Box<dyn Error>> {
@@ -418,45 +947,133 @@ impl CodeGenerator for RustGenerator {
             return Ok(());
         }
 
-        self.gen_doc(attr, "doc", output)?;
-        self.gen_rust_derive(attr, output)?;
-        self.gen_rust_attr(attr, "rust_attr", output, ctx, true)?;
+        // 公共字段要么放在包装结构体中，要么被内联到每一个构造器中，
+        // 此时标量变体也需要生成对应的结构体来存放公共字段
+        let has_common_fields = !sum_type.common_fields.is_empty();
+        let wrapper = has_common_fields && self.check_rust_wrapper(attr);
+        let inline_common_fields = has_common_fields && !wrapper;
+
+        let enum_name = if wrapper {
+            let enum_name = format!("{}Kind", sum_type.name);
+            self.gen_wrapper_struct(ctx, attr, sum_type, &enum_name, output)?;
+            enum_name
+        } else {
+            self.gen_doc(attr, "doc", output)?;
+            sum_type.name.clone()
+        };
+
+        // 作为键使用的和类型只包含标量变体，总是可以派生这些 trait
+        let key_traits: &[&str] = match (self.key_enums.contains(&sum_type.name), self.btree_collections) {
+            (false, _) => &[],
+            (true, false) => &["PartialEq", "Eq", "Hash"],
+            (true, true) => &["PartialEq", "Eq", "PartialOrd", "Ord"]
+        };
+        self.gen_rust_derive(attr, key_traits, output)?;
+        let tagging = ctx.sum_taggings.get(&sum_type.name);
+        let serde = self.check_rust_serde(attr);
+        self.gen_rust_attr(attr, "rust_attr", output, tagging)?;
         let private = check_private(attr);
 
         output.push_string(format!(
             "{}enum {} {{",
             if private { "" } else { "pub " },
-            sum_type.name
+            generic_type_name(&self.type_ident(&enum_name), &sum_type.type_params)
         ));
 
-        let mut enum_variants = Box::new(Doc::new(4));
+        // 标量变体（仅在内联公共字段时）和构造器对应的结构体，结构体只带有它使用到的类型参数
+        let to_struct = |name: &str, fields: Vec<Field>, span: &Span| {
+            let type_params = used_type_params(&sum_type.type_params, fields.iter())
+                .into_iter()
+                .map(ToString::to_string)
+                .collect();
+            TypeConstructor { name: name.to_string(), type_params, fields, span: span.clone() }
+        };
 
-        for (variant_attr, variant) in &sum_type.scalar_variants {
-            self.gen_doc(variant_attr, "doc", &mut enum_variants)?;
-            self.gen_rust_attr(variant_attr, "rust_attr", &mut enum_variants, ctx, false)?;
+        // 构造器的字段使用构造器上的命名风格，内联的公共字段则使用和类型上的命名风格
+        let sum_rule = rename_rule(attr);
+        let common_rules = vec![sum_rule; sum_type.common_fields.len()];
 
-            enum_variants.push_string(format!(
-                "{},",
-                variant
-            ));
+        let mut variant_structs = Vec::new();
+        if inline_common_fields {
+            for (variant_attr, variant, span) in &sum_type.scalar_variants {
+                variant_structs.push((
+                    variant_attr,
+                    to_struct(variant, sum_type.common_fields.clone(), span),
+                    common_rules.clone()
+                ));
+            }
         }
 
+        let mut ctor_structs = Vec::new();
         for (ctor_attr, ctor) in &sum_type.ctors {
-            self.gen_doc(ctor_attr, "doc", &mut enum_variants)?;
-            self.gen_rust_attr(ctor_attr, "rust_attr", &mut enum_variants, ctx, false)?;
-
-            if check_boxed(&ctor_attr) {
-                enum_variants.push_string(format!(
-                    "{}(Box<{}>),",
-                    ctor.name,
-                    ctor.name
-                ));
-            } else {
-                enum_variants.push_string(format!(
-                    "{}({}),",
-                    ctor.name,
-                    ctor.name
-                ));
+            let mut fields = ctor.fields.clone();
+            let mut rules = vec![rename_rule(ctor_attr).or(sum_rule); fields.len()];
+            if inline_common_fields {
+                fields.extend(sum_type.common_fields.iter().cloned());
+                rules.extend(common_rules.iter().copied());
+            }
+            ctor_structs.push((ctor_attr, to_struct(&ctor.name, fields, &ctor.span), rules));
+        }
+
+        let mut enum_variants = Box::new(Doc::new(4));
+
+        // 按照源代码中的顺序生成变体，untagged 表示方式下 serde 会按照变体的顺序逐个尝试
+        let mut variant_order = sum_type.scalar_variants
+            .iter()
+            .enumerate()
+            .map(|(idx, (_, _, span))| (span.line, span.col, false, idx))
+            .chain(ctor_structs.iter().enumerate().map(|(idx, (_, ctor, _))| (ctor.span.line, ctor.span.col, true, idx)))
+            .collect::<Vec<_>>();
+        variant_order.sort();
+
+        for (_, _, is_ctor, idx) in variant_order {
+            if is_ctor {
+                let (ctor_attr, ctor, _) = &ctor_structs[idx];
+                self.gen_doc(ctor_attr, "doc", &mut enum_variants)?;
+                self.gen_rust_attr(ctor_attr, "rust_attr", &mut enum_variants, None)?;
+                if serde {
+                    self.gen_serde_rename(tagging, &ctor.name, &mut enum_variants);
+                }
+
+                let ctor_ident = self.type_ident(&ctor.name);
+                if check_boxed(ctor_attr) {
+                    enum_variants.push_string(format!(
+                        "{}(Box<{}>),",
+                        ctor_ident,
+                        generic_type_name(&ctor_ident, &ctor.type_params)
+                    ));
+                } else {
+                    enum_variants.push_string(format!(
+                        "{}({}),",
+                        ctor_ident,
+                        generic_type_name(&ctor_ident, &ctor.type_params)
+                    ));
+                }
+                continue;
+            }
+
+            let (variant_attr, variant, _) = &sum_type.scalar_variants[idx];
+            self.gen_doc(variant_attr, "doc", &mut enum_variants)?;
+            self.gen_rust_attr(variant_attr, "rust_attr", &mut enum_variants, None)?;
+            if serde {
+                self.gen_serde_rename(tagging, variant, &mut enum_variants);
+            }
+
+            let variant_ident = self.type_ident(variant);
+            match variant_structs.iter().find(|(_, variant_struct, _)| &variant_struct.name == variant) {
+                Some((_, variant_struct, _)) => {
+                    enum_variants.push_string(format!(
+                        "{}({}),",
+                        variant_ident,
+                        generic_type_name(&variant_ident, &variant_struct.type_params)
+                    ));
+                },
+                None => {
+                    enum_variants.push_string(format!(
+                        "{},",
+                        variant_ident
+                    ));
+                }
             }
         }
 
@@ -465,12 +1082,13 @@ impl CodeGenerator for RustGenerator {
         output.push_str("}");
         output.push_empty_line();
 
-        for (ctor_attr, ctor) in &sum_type.ctors {
+        for (ctor_attr, ctor, rules) in variant_structs.iter().chain(ctor_structs.iter()) {
             self.imp_visit_simple_type(
                 ctx,
                 ctor_attr,
                 Some(attr),
                 ctor,
+                rules,
                 output,
                 "doc_ctor",
                 "rust_attr_ctor"
@@ -481,18 +1099,116 @@ impl CodeGenerator for RustGenerator {
     }
 }
 
+/// 去掉原始标识符的 `r#` 前缀，用于拼接关联函数的名称以及与线上名称比较
+fn unraw(ident: &str) -> &str {
+    ident.strip_prefix("r#").unwrap_or(ident)
+}
+
 pub struct RustGeneratorFactory();
 
 impl CodeGeneratorFactory for RustGeneratorFactory {
     fn generator_name(&self) -> &'static str {
-        RustGenerator().generator_name()
+        RustGenerator::default().generator_name()
     }
 
     fn lang_ident(&self) -> &'static str {
-        RustGenerator().lang_ident()
+        RustGenerator::default().lang_ident()
     }
 
     fn create(&self) -> Box<dyn CodeGenerator> {
-        Box::new(RustGenerator())
+        Box::new(RustGenerator::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::testing::{compile_err, compile_ok};
+
+    #[test]
+    fn default_impl_requires_defaultable_fields() {
+        let code = compile_ok("Shape : Circle | Square\nConfig(count: int = 0, shape: Shape)", "rust");
+        assert!(code.contains("fn default_count() -> i64"), "{}", code);
+        assert!(!code.contains("impl Default for Config"), "{}", code);
+
+        let code = compile_ok("Config(count: int = 0, name: str, tags: [str], note?: Note)\nNote(x: int)", "rust");
+        assert!(code.contains("impl Default for Config"), "{}", code);
+    }
+
+    #[test]
+    fn integer_default_of_float_alias_is_float() {
+        let code = compile_ok("Meters = float\nRoute(dist: Meters = 1, legs: [Meters] = [2])", "rust");
+        assert!(code.contains("        1.0\n"), "{}", code);
+        assert!(code.contains("vec![2.0]"), "{}", code);
+    }
+
+    #[test]
+    fn reserved_field_names_are_escaped() {
+        let code = compile_ok("[rust_serde]\nCall(fn: int, self: str, type: int = 1)", "rust");
+        assert!(code.contains("    pub r#fn: i64,"), "{}", code);
+        assert!(code.contains("#[serde(rename = \"self\")]\n    pub self_: String,"), "{}", code);
+        assert!(code.contains("#[serde(default = \"Call::default_type\")]\n    pub r#type: i64,"), "{}", code);
+        assert!(code.contains("r#type: Self::default_type(),"), "{}", code);
+    }
+
+    #[test]
+    fn reserved_type_names_are_escaped() {
+        let code = compile_ok("mod : Module(body: [match]) | Empty\nmatch : loop | while\nUse(m: mod, k: match = loop)", "rust");
+        assert!(code.contains("pub enum r#mod {"), "{}", code);
+        assert!(code.contains("pub body: Vec<r#match>,"), "{}", code);
+        assert!(code.contains("    r#loop,"), "{}", code);
+        assert!(code.contains("pub m: r#mod,"), "{}", code);
+        assert!(code.contains("r#match::r#loop"), "{}", code);
+    }
+
+    #[test]
+    fn unescapable_names_are_errors() {
+        let errors = compile_err("Self(x: int)", "rust");
+        assert!(errors[0].contains("无法转义的保留标识符"), "{:?}", errors);
+    }
+
+    #[test]
+    fn collections_use_full_paths_and_key_enums_derive_hash() {
+        let src = "[repr(external)]\nMode : Fast | Slow\nLevel : Low | High\nA(m: map<Mode, [int]>, s: set<Level>, t: set<str>)";
+        let code = compile_ok(src, "rust");
+        assert!(code.contains("pub m: ::std::collections::HashMap<Mode, Vec<i64>>,"), "{}", code);
+        assert!(code.contains("pub s: ::std::collections::HashSet<Level>,"), "{}", code);
+        assert!(code.contains("#[derive(Eq, Hash, PartialEq)]\npub enum Mode {"), "{}", code);
+        assert!(code.contains("#[derive(Eq, Hash, PartialEq)]\npub enum Level {"), "{}", code);
+
+        let code = compile_ok(&format!("[[rust_derive(Debug)]]\n{}", src.replace("Mode, [int]", "Mode, list<Mode>")), "rust");
+        assert!(code.contains("#[derive(Debug, Eq, Hash, PartialEq)]\npub enum Mode {"), "{}", code);
+    }
+
+    #[test]
+    fn tagging_representations() {
+        let code = compile_ok("[rust_serde]\nA : A1(v: int) | A2", "rust");
+        assert!(code.contains("#[serde(tag = \"$kind\")]\npub enum A {"), "{}", code);
+
+        let code = compile_ok("[rust_serde]\n[repr(adjacent)]\n[discriminant = \"t\"]\n[content = \"c\"]\nB : B1(v: int) | B2", "rust");
+        assert!(code.contains("#[serde(tag = \"t\", content = \"c\")]\npub enum B {"), "{}", code);
+
+        let code = compile_ok("[rust_serde]\n[repr(untagged)]\nC : C1(v: int) | C2(w: str)", "rust");
+        assert!(code.contains("#[serde(untagged)]\npub enum C {"), "{}", code);
+
+        let code = compile_ok("[rust_serde]\n[repr(external)]\nD : D1(v: int) | D2 | D3(w: str)", "rust");
+        assert!(code.contains("    D1(D1),\n    D2,\n    D3(D3),"), "{}", code);
+        assert!(code.contains("#[derive(Deserialize, Serialize)]\npub enum D {"), "{}", code);
+    }
+
+    #[test]
+    fn optional_and_nullable_fields() {
+        let code = compile_ok("[rust_serde]\nO(a?: int, b: int | null, c?: int | null)", "rust");
+        assert!(code.contains("#[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub a: Option<i64>,"), "{}", code);
+        assert!(code.contains("\n    pub b: Option<i64>,"), "{}", code);
+        assert!(code.contains("deserialize_with = \"O::deserialize_c\")]\n    pub c: Option<Option<i64>>,"), "{}", code);
+        assert!(code.contains("fn deserialize_c<'de, D>"), "{}", code);
+    }
+
+    #[test]
+    fn deserialize_helpers_do_not_depend_on_user_types() {
+        let code = compile_ok("Result<T, E> : Ok(value: T) | Err(error: E)\n[rust_serde]\nO(c?: int | null, r: Result<int, str>)", "rust");
+        assert!(code.contains("-> ::std::result::Result<Option<Option<i64>>, D::Error>"), "{}", code);
+        assert!(code.contains("D: ::serde::Deserializer<'de>,"), "{}", code);
+        assert!(code.contains("::serde::Deserialize::deserialize(deserializer).map(::std::option::Option::Some)"), "{}", code);
     }
 }
@@ -3,69 +3,117 @@
 use std::error::Error;
 
 use crate::{
+    attr_spec::{AttrPosition, AttrShape, AttrSpec},
     codegen::{
         CodeGenerator,
         CodeGeneratorFactory,
-        Doc
+        Doc,
+        IdentContext,
+        generic_type_name
     },
     parser::hir::{
         SumType,
         RSDLType,
-        AttrItem,
+        Attr,
+        AttrQuery,
+        Field,
+        Literal,
         TypeConstructor,
-        check_ident_attr,
         check_inline,
         check_private,
-        extract_doc_strings
+        extract_doc_strings,
+        used_type_params
     },
-    min_resolv::ResolveContext
+    min_resolv::{MapKeyKind, ResolveContext, SumRepr, SumTagging},
+    naming::{rename_rule, RenameRule}
 };
 
 /// 基于 `interface` 的 TypeScript 代码生成器
-pub struct TSInterfaceGenerator();
+#[derive(Debug, Default)]
+pub struct TSInterfaceGenerator {
+    /// 通过 `header` 选项指定的，在生成的代码开头输出的内容
+    header: Vec<String>,
+    /// 正在生成的和类型是否带有 `ts_readonly` 注解，用于生成和类型的构造器
+    sum_type_readonly: bool,
+    /// 正在生成的和类型上 `rename_all` 注解指定的命名风格，用于生成和类型的构造器
+    sum_type_rename_rule: Option<RenameRule>
+}
 
 impl TSInterfaceGenerator {
     fn type_to_string(&self, ctx: &ResolveContext, ty: &RSDLType) -> Option<String> {
         match ty {
             RSDLType::Identifier(ident) => {
-                if let Some((_, rsdl_type, is_inline)) = ctx.known_types.get(ident) {
-                    if *is_inline {
-                        let Some(rsdl_type) = rsdl_type else { return None; };
+                if let Some(known_type) = ctx.known_types.get(ident) {
+                    if known_type.is_inline {
+                        let Some(rsdl_type) = &known_type.aliased else { return None; };
                         self.type_to_string(ctx, rsdl_type)
                     } else {
                         Some(ident.to_string())
                     }
                 } else {
-                    None
+                    // 通过了检查的类型中，只有类型参数不在已知类型表中
+                    Some(ident.to_string())
                 }
             },
+            RSDLType::Generic(ident, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.type_to_string(ctx, arg))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(generic_type_name(ident, &args))
+            },
             RSDLType::Native(native) => {
-                if let Some(ts_name) = native.get("ts") {
-                    Some(ts_name.to_string())
-                } else if let Some(ts_name) = native.get("typescript") {
-                    Some(ts_name.to_string())
-                } else {
-                    None
-                }
+                native.get("ts")
+                    .or_else(|| native.get("typescript"))
+                    .map(|ts_name| ts_name.to_string())
             },
             RSDLType::List(inner) => {
-                let inner = self.type_to_string(ctx, &inner)?;
+                let inner = self.type_to_string(ctx, inner)?;
                 Some(format!("{}[]", inner))
             },
-            RSDLType::Record(inner) => {
-                let inner = self.type_to_string(ctx, &inner)?;
-                Some(format!("Record<string, {}>", inner))
+            RSDLType::Array(inner, len) => {
+                let inner = self.type_to_string(ctx, inner)?;
+                Some(format!("[{}]", vec![inner; *len].join(", ")))
+            },
+            RSDLType::Tuple(items) => {
+                let items = items
+                    .iter()
+                    .map(|item| self.type_to_string(ctx, item))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(format!("[{}]", items.join(", ")))
+            },
+            RSDLType::Map(key, value) => {
+                let value = self.type_to_string(ctx, value)?;
+                // 生成的类型描述的是 JSON.parse 的结果：映射总是普通对象，对象的键总是字符串。
+                // 作为键的和类型使用 external 表示方式（由编译器检查），
+                // 此时和类型本身就是标量变体名称组成的字符串字面量联合类型
+                match ctx.map_key_kind(key) {
+                    Some(MapKeyKind::Integer) => Some(format!("Record<number, {}>", value)),
+                    Some(MapKeyKind::Enum(name)) => Some(format!("Partial<Record<{}, {}>>", name, value)),
+                    Some(MapKeyKind::String) | Some(MapKeyKind::Native) | None => {
+                        Some(format!("Record<string, {}>", value))
+                    }
+                }
+            },
+            RSDLType::Set(inner) => {
+                // 集合在 JSON 中是数组
+                let inner = self.type_to_string(ctx, inner)?;
+                Some(format!("{}[]", inner))
             }
         }
     }
 
-    fn check_ts_skip(&self, attr_list: &[AttrItem]) -> bool {
-        check_ident_attr(attr_list, "typescript_skip") || check_ident_attr(attr_list, "ts_skip")
+    fn check_ts_skip(&self, attr_list: &[Attr]) -> bool {
+        attr_list.flag("typescript_skip") || attr_list.flag("ts_skip")
+    }
+
+    fn check_ts_readonly(&self, attr_list: &[Attr]) -> bool {
+        attr_list.flag("ts_readonly")
     }
 
     fn gen_doc(
         &self,
-        attr_list: &[AttrItem],
+        attr_list: &[Attr],
         doc_attr_names: &[&str],
         output: &mut Doc
     ) -> Result<(), Box<dyn Error>> {
@@ -91,59 +139,276 @@ impl TSInterfaceGenerator {
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn imp_visit_simple_type(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         type_ctor: &TypeConstructor,
         output: &mut Doc,
 
         doc_attr_names: &[&str],
-        parent: Option<&str>,
+        parent: Option<&SumType>,
+        parent_readonly: bool,
+        rule: Option<RenameRule>
     ) -> Result<(), Box<dyn Error>> {
         if self.check_ts_skip(attr) {
             return Ok(());
         }
         self.gen_doc(attr, doc_attr_names, output)?;
         let private = check_private(attr);
+        let readonly = parent_readonly || self.check_ts_readonly(attr);
 
-        if let Some(parent) = parent {
+        let type_params = match parent {
+            Some(parent) => self.variant_type_params(parent, &type_ctor.fields),
+            None => type_ctor.type_params.clone()
+        };
+
+        let base_type = parent.and_then(|parent| self.base_type_name(ctx, parent, &type_ctor.name));
+        if let Some(base_type) = base_type {
             output.push_string(format!(
-                "{}interface {} extends {}Base<\"{}\"> {{",
+                "{}interface {} extends {} {{",
                 if private { "" } else { "export " },
-                type_ctor.name,
-                parent,
-                type_ctor.name
+                generic_type_name(&type_ctor.name, &type_params),
+                base_type
             ));
         } else {
             output.push_string(format!(
                 "{}interface {} {{",
                 if private { "" } else { "export " },
-                type_ctor.name
+                generic_type_name(&type_ctor.name, &type_params)
             ));
         }
 
         let mut fields = Box::new(Doc::new(4));
-        for (attr, optional, field_type, field_name) in &type_ctor.fields {
-            self.gen_doc(attr, &["doc"], &mut fields)?;
+        self.gen_fields(ctx, &type_ctor.fields, rule, readonly, &mut fields)?;
+        output.push_doc(fields);
 
-            let inner_type = self.type_to_string(ctx, field_type)
-                .ok_or("RSDL native 类型缺少对应的 Typescript 类型")?;
+        output.push_str("}");
+        output.push_empty_line();
+
+        let mut all_fields = type_ctor.fields
+            .iter()
+            .map(|field| (rule, field))
+            .collect::<Vec<_>>();
+        if let Some(parent) = parent {
+            all_fields.extend(parent.common_fields.iter().map(|field| (self.sum_type_rename_rule, field)));
+        }
+        let tag = parent.and_then(|parent| self.internal_tag(ctx, parent, &type_ctor.name));
+        self.gen_factory(
+            ctx,
+            &type_ctor.name,
+            &type_params,
+            tag,
+            &all_fields,
+            private,
+            output
+        )?;
+
+        Ok(())
+    }
+
+    /// 为带有默认值的字段的类型生成工厂函数 `makeXxx`，带有默认值的字段在参数中是可选的。
+    /// 如果 `tag` 不为空，则生成的对象会带有 `tag` 指定的标签字段和标签值。
+    /// `fields` 中的每个字段都带有它所在的类型（或者构造器）指定的命名风格
+    #[allow(clippy::too_many_arguments)]
+    fn gen_factory(
+        &self,
+        ctx: &ResolveContext,
+        type_name: &str,
+        type_params: &[String],
+        tag: Option<(&str, &str)>,
+        fields: &[(Option<RenameRule>, &Field)],
+        private: bool,
+        output: &mut Doc
+    ) -> Result<(), Box<dyn Error>> {
+        let defaulted_fields = fields
+            .iter()
+            .filter_map(|(rule, field)| field.default
+                .as_ref()
+                .map(|default| (self.field_name(*rule, field), *field, default)))
+            .collect::<Vec<_>>();
+
+        if defaulted_fields.is_empty() {
+            return Ok(());
+        }
 
-            fields.push_string(format!(
-                "{}{}: {},",
-                field_name,
-                if *optional { "?" } else { "" },
-                inner_type
+        let defaulted_names = defaulted_fields
+            .iter()
+            .map(|(name, _, _)| format!("{:?}", name))
+            .collect::<Vec<_>>()
+            .join(" | ");
+        let omitted_names = match tag {
+            Some((discriminant, _)) => format!("{:?} | {}", discriminant, defaulted_names),
+            None => defaulted_names.clone()
+        };
+
+        let generic_name = generic_type_name(type_name, type_params);
+        output.push_string(format!(
+            "{}function {}(init: Omit<{}, {}> & Partial<Pick<{}, {}>>): {} {{",
+            if private { "" } else { "export " },
+            generic_type_name(&format!("make{}", type_name), type_params),
+            generic_name,
+            omitted_names,
+            generic_name,
+            defaulted_names,
+            generic_name
+        ));
+
+        let mut body = Box::new(Doc::new(4));
+        body.push_str("return {");
+        let mut values = Box::new(Doc::new(4));
+        if let Some((discriminant, tag_value)) = tag {
+            values.push_string(format!("{:?}: {:?},", discriminant, tag_value));
+        }
+        for (name, field, default) in defaulted_fields {
+            values.push_string(format!(
+                "{}: {},",
+                property_name(&name),
+                self.literal_to_string(ctx, &field.ty, &default.value)
             ));
         }
-        output.push_doc(fields);
+        values.push_str("...init");
+        body.push_doc(values);
+        body.push_str("};");
+        output.push_doc(body);
 
         output.push_str("}");
         output.push_empty_line();
 
         Ok(())
     }
+
+    /// 和类型的构造器或者标量变体对应的 interface 的类型参数，即它的字段和公共字段使用到的类型参数
+    fn variant_type_params(&self, sum_type: &SumType, fields: &[Field]) -> Vec<String> {
+        used_type_params(&sum_type.type_params, fields.iter().chain(sum_type.common_fields.iter()))
+            .into_iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// 和类型的构造器或者标量变体继承的 `XxxBase<"Variant", ...>` interface。
+    /// 不使用 internal 表示方式时，标签不在 interface 中，`XxxBase` 只带有公共字段使用到的类型参数；
+    /// 此时如果没有公共字段，则不需要 `XxxBase`，返回 `None`
+    fn base_type_name(&self, ctx: &ResolveContext, sum_type: &SumType, variant: &str) -> Option<String> {
+        let mut base_args = Vec::new();
+        match self.internal_tag(ctx, sum_type, variant) {
+            Some((_, tag_value)) => base_args.push(format!("{:?}", tag_value)),
+            None if sum_type.common_fields.is_empty() => return None,
+            None => {}
+        }
+        base_args.extend(
+            used_type_params(&sum_type.type_params, sum_type.common_fields.iter())
+                .into_iter()
+                .map(ToString::to_string)
+        );
+        Some(generic_type_name(&format!("{}Base", sum_type.name), &base_args))
+    }
+
+    /// 和类型的变体在联合类型中的写法，`content` 是变体对应的 interface，标量变体没有内容时为 `None`
+    fn tagged_variant(&self, tagging: &SumTagging, variant: &str, content: Option<String>) -> String {
+        let tag_value = tagging.tag_value(variant);
+
+        match (tagging.repr, content) {
+            (SumRepr::Internal | SumRepr::Untagged, Some(content)) => content,
+            (SumRepr::Adjacent, Some(content)) => format!(
+                "{{ {:?}: {:?}, {:?}: {} }}",
+                tagging.discriminant,
+                tag_value,
+                tagging.content,
+                content
+            ),
+            (SumRepr::Adjacent, None) => format!("{{ {:?}: {:?} }}", tagging.discriminant, tag_value),
+            (SumRepr::External, Some(content)) => format!("{{ {:?}: {} }}", tag_value, content),
+            (SumRepr::External, None) => format!("{:?}", tag_value),
+            // internal 表示方式的标量变体总是有对应的 interface，untagged 表示方式的标量变体会被 min_resolv 拒绝
+            (SumRepr::Internal | SumRepr::Untagged, None) => "null".to_string()
+        }
+    }
+
+    /// 使用 internal 表示方式时，和类型的变体对应的标签字段名称和标签值
+    fn internal_tag<'a>(
+        &self,
+        ctx: &'a ResolveContext,
+        sum_type: &SumType,
+        variant: &'a str
+    ) -> Option<(&'a str, &'a str)> {
+        ctx.sum_taggings
+            .get(&sum_type.name)
+            .filter(|tagging| tagging.repr == SumRepr::Internal)
+            .map(|tagging| (tagging.discriminant.as_str(), tagging.tag_value(variant)))
+    }
+
+    /// `ty` 是字面量的类型，用于确定标量变体的表示方式以及列表中元素的类型
+    fn literal_to_string(&self, ctx: &ResolveContext, ty: &RSDLType, literal: &Literal) -> String {
+        match literal {
+            Literal::Integer(i) => i.to_string(),
+            Literal::Float(f) => format!("{:?}", f),
+            Literal::Bool(b) => b.to_string(),
+            Literal::String(s) => format!("{:?}", s),
+            Literal::Variant(variant) => {
+                let tagging = match ctx.resolve_alias(ty) {
+                    RSDLType::Identifier(ident) => ctx.sum_taggings.get(ident),
+                    _ => None
+                };
+                match tagging {
+                    Some(tagging) if tagging.repr == SumRepr::External => {
+                        format!("{:?}", tagging.tag_value(variant))
+                    },
+                    Some(tagging) => {
+                        format!("{{ {:?}: {:?} }}", tagging.discriminant, tagging.tag_value(variant))
+                    },
+                    None => format!("{{ {:?}: {:?} }}", ctx.discriminant, variant)
+                }
+            },
+            Literal::List(items) => {
+                let items = match ctx.resolve_alias(ty) {
+                    RSDLType::Tuple(types) => types
+                        .iter()
+                        .zip(items)
+                        .map(|(ty, item)| self.literal_to_string(ctx, ty, item))
+                        .collect::<Vec<_>>(),
+                    RSDLType::List(inner) | RSDLType::Array(inner, _) => items
+                        .iter()
+                        .map(|item| self.literal_to_string(ctx, inner, item))
+                        .collect::<Vec<_>>(),
+                    _ => items
+                        .iter()
+                        .map(|item| self.literal_to_string(ctx, ty, item))
+                        .collect::<Vec<_>>()
+                };
+
+                format!("[{}]", items.join(", "))
+            }
+        }
+    }
+
+    fn gen_fields(
+        &self,
+        ctx: &ResolveContext,
+        fields: &[Field],
+        rule: Option<RenameRule>,
+        readonly: bool,
+        output: &mut Doc
+    ) -> Result<(), Box<dyn Error>> {
+        for field in fields {
+            self.gen_doc(&field.attr, &["doc"], output)?;
+
+            let inner_type = self.type_to_string(ctx, &field.ty)
+                .ok_or("RSDL native 类型缺少对应的 Typescript 类型")?;
+
+            output.push_string(format!(
+                "{}{}{}: {}{},",
+                if readonly || self.check_ts_readonly(&field.attr) { "readonly " } else { "" },
+                property_name(&self.field_name(rule, field)),
+                if field.optionality.is_optional() { "?" } else { "" },
+                inner_type,
+                if field.optionality.is_nullable() { " | null" } else { "" }
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 impl CodeGenerator for TSInterfaceGenerator {
@@ -209,6 +474,27 @@ impl CodeGenerator for TSInterfaceGenerator {
         ]
     }
 
+    /// 保留字可以直接作为属性名称（例如 `{ type: string }`），因此字段名称不需要转义；
+    /// 类型名称中的保留字无法转义
+    fn escape_ident(&self, context: IdentContext, ident: &str) -> Option<String> {
+        match context {
+            IdentContext::FieldName => Some(ident.to_string()),
+            IdentContext::TypeName | IdentContext::Namespace => None
+        }
+    }
+
+    fn known_attrs(&self) -> &[AttrSpec] {
+        use AttrPosition::*;
+
+        const KNOWN_ATTRS: &[AttrSpec] = &[
+            AttrSpec::new("ts_skip", &[Type, Ctor], AttrShape::Flag),
+            AttrSpec::new("typescript_skip", &[Type, Ctor], AttrShape::Flag),
+            AttrSpec::new("ts_readonly", &[Type, Field, Ctor], AttrShape::Flag)
+        ];
+
+        KNOWN_ATTRS
+    }
+
     fn visit_namespace_begin(
         &mut self,
         _namespace: &str,
@@ -225,10 +511,34 @@ impl CodeGenerator for TSInterfaceGenerator {
         unreachable!()
     }
 
+    fn set_option(&mut self, name: &str, value: &str) -> Result<(), Box<dyn Error>> {
+        match name {
+            "header" => {
+                self.header = value.lines().map(ToString::to_string).collect();
+                Ok(())
+            },
+            _ => Err(format!("不支持选项 {}", name).into())
+        }
+    }
+
+    fn pre_visit(
+        &mut self,
+        _ctx: &ResolveContext,
+        output: &mut Doc
+    ) -> Result<(), Box<dyn Error>> {
+        if !self.header.is_empty() {
+            for line in self.header.iter() {
+                output.push_string(line.clone());
+            }
+            output.push_empty_line();
+        }
+        Ok(())
+    }
+
     fn visit_type_alias(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         alias_name: &str,
         target_type: &RSDLType,
         output: &mut Doc
@@ -255,7 +565,7 @@ impl CodeGenerator for TSInterfaceGenerator {
     fn visit_simple_type(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         type_ctor: &TypeConstructor,
         output: &mut Doc
     ) -> Result<(), Box<dyn Error>> {
@@ -265,14 +575,16 @@ impl CodeGenerator for TSInterfaceGenerator {
             type_ctor,
             output,
             &["doc"],
-            None
+            None,
+            false,
+            rename_rule(attr)
         )
     }
 
     fn visit_sum_type_ctor(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         ctor: &TypeConstructor,
         sum_type: &SumType,
         output: &mut Doc
@@ -283,14 +595,16 @@ impl CodeGenerator for TSInterfaceGenerator {
             ctor,
             output,
             &["doc", "doc_ctor"],
-            Some(sum_type.name.as_str())
+            Some(sum_type),
+            self.sum_type_readonly,
+            rename_rule(attr).or(self.sum_type_rename_rule)
         )
     }
 
     fn visit_sum_type_scalar_variant(
         &mut self,
         _ctx: &ResolveContext,
-        _attr: &[AttrItem],
+        _attr: &[Attr],
         _variant_name: &str,
         _sum_type: &SumType,
         _output: &mut Doc
@@ -301,83 +615,182 @@ impl CodeGenerator for TSInterfaceGenerator {
     fn visit_sum_type(
         &mut self,
         ctx: &ResolveContext,
-        attr: &[AttrItem],
+        attr: &[Attr],
         sum_type: &SumType,
         output: &mut Doc
     ) -> Result<(), Box<dyn Error>> {
+        self.sum_type_readonly = self.check_ts_readonly(attr);
+        self.sum_type_rename_rule = rename_rule(attr);
         if self.check_ts_skip(attr) {
             return Ok(());
         }
 
         self.gen_doc(attr, &["doc"], output)?;
         let private = check_private(attr);
+        let tagging = ctx.sum_taggings
+            .get(&sum_type.name)
+            .ok_or_else(|| format!("未知的和类型 {}", sum_type.name))?;
+        let internal = tagging.repr == SumRepr::Internal;
+        // 不使用 internal 表示方式并且没有公共字段时，标量变体没有内容，不需要单独的 interface
+        let variant_interfaces = internal || !sum_type.common_fields.is_empty();
 
         output.push_string(format!(
             "{}type {} = ",
             if private { "" } else { "export " },
-            sum_type.name
+            generic_type_name(&sum_type.name, &sum_type.type_params)
         ));
         
+        let variant_type_params = self.variant_type_params(sum_type, &[]);
         let mut sum_variants = Box::new(Doc::new(4));
-        for (_, variant) in &sum_type.scalar_variants {
+        for (_, variant, _) in &sum_type.scalar_variants {
+            let content = variant_interfaces.then(|| generic_type_name(variant, &variant_type_params));
             sum_variants.push_string(format!(
                 "| {}",
-                variant
+                self.tagged_variant(tagging, variant, content)
             ));
         }
 
         for (_, ctor) in &sum_type.ctors {
+            let content = generic_type_name(&ctor.name, &self.variant_type_params(sum_type, &ctor.fields));
             sum_variants.push_string(format!(
                 "| {}",
-                ctor.name,
+                self.tagged_variant(tagging, &ctor.name, Some(content))
             ));
         }
         output.push_doc(sum_variants);
 
         output.push_empty_line();
 
-        output.push_string(format!(
-            "{}interface {}Base<K extends string> {{",
-            if private { "" } else { "export " },
-            sum_type.name
-        ));
-        let mut fields = Box::new(Doc::new(4));
-        fields.push_string(format!("{}: K;", ctx.discriminant));
-        output.push_doc(fields);
-        output.push_string("}".to_string());
+        if variant_interfaces {
+            let mut base_params = Vec::new();
+            if internal {
+                base_params.push("K extends string".to_string());
+            }
+            base_params.extend(variant_type_params.iter().cloned());
+            output.push_string(format!(
+                "{}interface {} {{",
+                if private { "" } else { "export " },
+                generic_type_name(&format!("{}Base", sum_type.name), &base_params)
+            ));
+            let mut fields = Box::new(Doc::new(4));
+            if internal {
+                fields.push_string(format!(
+                    "{}{}: K;",
+                    if self.sum_type_readonly { "readonly " } else { "" },
+                    property_name(&tagging.discriminant)
+                ));
+            }
+            self.gen_fields(ctx, &sum_type.common_fields, self.sum_type_rename_rule, self.sum_type_readonly, &mut fields)?;
+            output.push_doc(fields);
+            output.push_string("}".to_string());
+        }
+
+        if variant_interfaces && !sum_type.scalar_variants.is_empty() {
+            output.push_empty_line();
+
+            for (variant_attr, variant, _) in &sum_type.scalar_variants {
+                self.gen_doc(variant_attr, &["doc", "doc_ctor"], output)?;
+                output.push_string(format!(
+                    "{}interface {} extends {} {{}}",
+                    if private { "" } else { "export " },
+                    generic_type_name(variant, &variant_type_params),
+                    self.base_type_name(ctx, sum_type, variant).unwrap_or_default()
+                ));
+            }
+        }
 
-        if !sum_type.scalar_variants.is_empty() {
+        if variant_interfaces {
             output.push_empty_line();
         }
 
-        for (variant_attr, variant) in &sum_type.scalar_variants {
-            self.gen_doc(variant_attr, &["doc", "doc_ctor"], output)?;
-            output.push_string(format!(
-                "{}interface {} extends {}Base<\"{}\"> {{}}",
-                if private { "" } else { "export " },
-                variant,
-                sum_type.name,
-                variant
-            ));
+        let common_fields = sum_type.common_fields
+            .iter()
+            .map(|field| (self.sum_type_rename_rule, field))
+            .collect::<Vec<_>>();
+        for (_, variant, _) in &sum_type.scalar_variants {
+            let tag = self.internal_tag(ctx, sum_type, variant);
+            self.gen_factory(ctx, variant, &variant_type_params, tag, &common_fields, private, output)?;
         }
 
-        output.push_empty_line();
         Ok(())
     }
 }
 
+/// 属性名称是合法的标识符时原样返回，否则加上引号
+fn property_name(name: &str) -> String {
+    let is_ident = name
+        .chars()
+        .enumerate()
+        .all(|(idx, c)| c == '_' || c == '$' || c.is_ascii_alphabetic() || (idx > 0 && c.is_ascii_digit()));
+
+    if is_ident && !name.is_empty() {
+        name.to_string()
+    } else {
+        format!("{:?}", name)
+    }
+}
+
 pub struct TSInterfaceGeneratorFactory();
 
 impl CodeGeneratorFactory for TSInterfaceGeneratorFactory {
     fn generator_name(&self) -> &'static str {
-        TSInterfaceGenerator().generator_name()
+        TSInterfaceGenerator::default().generator_name()
     }
 
     fn lang_ident(&self) -> &'static str {
-        TSInterfaceGenerator().lang_ident()
+        TSInterfaceGenerator::default().lang_ident()
     }
 
     fn create(&self) -> Box<dyn CodeGenerator> {
-        Box::new(TSInterfaceGenerator())
+        Box::new(TSInterfaceGenerator::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::testing::compile_ok;
+
+    #[test]
+    fn map_key_types() {
+        let code = compile_ok(
+            "[repr(external)]\nMode : Fast | Slow\nA(a: map<str, int>, b: map<int, str>, c: map<Mode, bool>, d: set<str>)",
+            "typescript"
+        );
+        assert!(code.contains("a: Record<string, number>,"), "{}", code);
+        assert!(code.contains("b: Record<number, string>,"), "{}", code);
+        assert!(code.contains("c: Partial<Record<Mode, boolean>>,"), "{}", code);
+        assert!(code.contains("d: string[],"), "{}", code);
+
+        let code = compile_ok("Id = native(rust => \"u64\", typescript => \"bigint\")\nA(a: map<Id, str>)", "typescript");
+        assert!(code.contains("a: Record<string, string>,"), "{}", code);
+    }
+
+    #[test]
+    fn reserved_words_are_property_names() {
+        let code = compile_ok("Call(function: int, class: str = \"a\")", "typescript");
+        assert!(code.contains("    function: number,\n    class: string,"), "{}", code);
+        assert!(code.contains("class: \"a\","), "{}", code);
+    }
+
+    #[test]
+    fn tagging_representations() {
+        let code = compile_ok("A : A1(v: int) | A2", "typescript");
+        assert!(code.contains("export interface A1 extends ABase<\"A1\"> {"), "{}", code);
+        assert!(code.contains("export interface A2 extends ABase<\"A2\"> {}"), "{}", code);
+
+        let code = compile_ok("[repr(adjacent)]\n[discriminant = \"t\"]\n[content = \"c\"]\nB : B1(v: int) | B2", "typescript");
+        assert!(code.contains("    | { \"t\": \"B2\" }\n    | { \"t\": \"B1\", \"c\": B1 }"), "{}", code);
+
+        let code = compile_ok("[repr(untagged)]\nC : C1(v: int) | C2(w: str)", "typescript");
+        assert!(code.contains("    | C1\n    | C2"), "{}", code);
+
+        let code = compile_ok("[repr(external)]\nD : D1(v: int) | D2", "typescript");
+        assert!(code.contains("    | \"D2\"\n    | { \"D1\": D1 }"), "{}", code);
+    }
+
+    #[test]
+    fn optional_and_nullable_fields() {
+        let code = compile_ok("O(a?: int, b: int | null, c?: int | null)", "typescript");
+        assert!(code.contains("    a?: number,\n    b: number | null,\n    c?: number | null,"), "{}", code);
     }
 }
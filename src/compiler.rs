@@ -0,0 +1,521 @@
+//! 可嵌入的 `rsdl` 编译器接口
+//!
+//! 与 [`crate::driver::application_start`] 不同，这个模块中的函数不会读取命令行参数、
+//! 初始化日志系统或者写入文件，所有的输入都通过 [`CompileOptions`] 和 [`FileProvider`]
+//! 提供，所有的输出都通过返回值交给调用者，因此可以安全地嵌入到其他程序或者测试中。
+//!
+//! # 示例
+//!
+//! ```rust
+//! use rsdl::codegen::rustgen::RustGeneratorFactory;
+//! use rsdl::compiler::{CompileOptions, MemoryFileProvider};
+//!
+//! let mut files = MemoryFileProvider::new();
+//! files.add("point.rsdl", "Point(x: int, y: int)");
+//!
+//! let mut options = CompileOptions::new("point.rsdl");
+//! options.targets.push("rust".to_string());
+//!
+//! let output = rsdl::compile(options, &files, &[&RustGeneratorFactory()]).unwrap();
+//! assert!(output.outputs[0].code.contains("pub struct Point"));
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use pest::Parser;
+use tracing::{info, warn};
+
+use crate::attr_spec::CORE_ATTRS;
+use crate::codegen::{codegen, CodeGeneratorFactory};
+use crate::diagnostic::{Diagnostic, Diagnostics, SourceMap};
+use crate::driver::REFERENTIAL_STDLIB;
+use crate::min_resolv::ResolveContext;
+use crate::parser::asdl::{self, asdlconv, is_asdl_module, PestASDLParser};
+use crate::parser::pest_parser::{PestRSDLParser, Rule};
+use crate::parser::treeconv::treeconv;
+use crate::preprocess::preprocess;
+
+/// 编译器读取源文件的接口
+///
+/// 编译器不会直接访问文件系统，而是通过这个接口读取根输入文件、`#include` 的文件以及自定义的标准库
+pub trait FileProvider {
+    /// 读取一个文件的全部内容
+    fn read(&self, path: &Path) -> io::Result<String>;
+
+    /// 将路径规范化
+    ///
+    /// 编译器使用规范化之后的路径判断两个 `#include` 是否指向同一个文件。
+    /// 如果文件不存在，应该返回错误
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+}
+
+/// 从文件系统中读取源文件
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsFileProvider;
+
+impl FileProvider for FsFileProvider {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        path.canonicalize()
+    }
+}
+
+/// 从内存中读取源文件
+///
+/// 适用于测试，或者源代码并不来自文件系统的场合
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFileProvider {
+    files: HashMap<PathBuf, String>
+}
+
+impl MemoryFileProvider {
+    /// 创建一个空的内存文件表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 向内存文件表中添加一个文件
+    pub fn add(&mut self, path: impl AsRef<Path>, src: impl ToString) -> &mut Self {
+        self.files.insert(normalize_path(path.as_ref()), src.to_string());
+        self
+    }
+}
+
+impl FileProvider for MemoryFileProvider {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        self.files
+            .get(&normalize_path(path))
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "文件不存在"))
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let path = normalize_path(path);
+        if self.files.contains_key(&path) {
+            Ok(path)
+        } else {
+            Err(io::Error::new(io::ErrorKind::NotFound, "文件不存在"))
+        }
+    }
+}
+
+/// 在不访问文件系统的情况下，移除路径中的 `.` 并展开 `..`
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => { normalized.pop(); },
+            _ => normalized.push(component)
+        }
+    }
+    normalized
+}
+
+/// 编译选项
+#[derive(Debug, Clone)]
+pub struct CompileOptions {
+    /// 根输入文件
+    pub input: PathBuf,
+    /// 要生成的目标语言标识符，参见 [`CodeGeneratorFactory::lang_ident`]
+    pub targets: Vec<String>,
+    /// 生成代码所在的名称空间
+    pub namespace: Option<String>,
+    /// 和类型的 `discriminant` 名称
+    pub discriminant: String,
+    /// 自定义的标准库文件，通过 [`FileProvider`] 读取。指定时 `prebuilt_stdlib` 不会被使用
+    pub stdlib: Option<PathBuf>,
+    /// 预编译的标准库源代码
+    pub prebuilt_stdlib: String,
+    /// 每个代码生成器的选项，以语言标识符为键，参见 [`crate::codegen::CodeGenerator::set_option`]
+    pub generator_options: HashMap<String, BTreeMap<String, String>>
+}
+
+impl CompileOptions {
+    /// 创建一个以 `input` 为根输入文件的编译选项
+    ///
+    /// 默认不生成任何目标，`discriminant` 为 `$kind`，使用 [`REFERENTIAL_STDLIB`] 作为标准库
+    pub fn new(input: impl Into<PathBuf>) -> Self {
+        Self {
+            input: input.into(),
+            targets: Vec::new(),
+            namespace: None,
+            discriminant: "$kind".to_string(),
+            stdlib: None,
+            prebuilt_stdlib: REFERENTIAL_STDLIB.to_string(),
+            generator_options: HashMap::new()
+        }
+    }
+}
+
+/// 一个目标语言的生成结果
+#[derive(Debug, Clone)]
+pub struct TargetOutput {
+    /// 目标语言标识符
+    pub lang_ident: String,
+    /// 生成的代码
+    pub code: String
+}
+
+/// 编译结果
+#[derive(Debug, Clone)]
+pub struct CompileOutput {
+    /// 每个目标语言的生成结果，顺序与 [`CompileOptions::targets`] 一致
+    pub outputs: Vec<TargetOutput>,
+    /// 编译过程中读取的所有文件，包括根输入文件、所有 `#include` 的文件以及自定义的标准库
+    pub files: Vec<PathBuf>,
+    /// 编译过程中产生的警告
+    pub warnings: Diagnostics
+}
+
+/// 编译 RSDL 源代码
+///
+/// 这个函数只会通过 `files` 读取源代码，不会产生任何其他副作用。
+/// 如果编译失败，返回编译过程中遇到的所有错误。如果需要带有代码片段的错误信息，
+/// 请使用 [`compile_with_source_map`]
+pub fn compile(
+    options: CompileOptions,
+    files: &dyn FileProvider,
+    generators: &[&dyn CodeGeneratorFactory]
+) -> Result<CompileOutput, Diagnostics> {
+    compile_with_source_map(options, files, generators, &mut SourceMap::new())
+}
+
+/// 编译 RSDL 源代码，并将读取到的源代码记录在 `source_map` 中
+///
+/// 编译失败时，可以使用 `source_map` 调用 [`Diagnostics::render`] 来输出带有代码片段的错误信息
+pub fn compile_with_source_map(
+    options: CompileOptions,
+    files: &dyn FileProvider,
+    generators: &[&dyn CodeGeneratorFactory],
+    source_map: &mut SourceMap
+) -> Result<CompileOutput, Diagnostics> {
+    let mut diags = Diagnostics::new();
+
+    let mut target_generators = Vec::new();
+    for target in options.targets.iter() {
+        match generators.iter().find(|generator| generator.lang_ident() == target.as_str()) {
+            Some(generator) => target_generators.push(*generator),
+            None => diags.push(Diagnostic::without_span(format!("不支持的输出模式 {}", target)))
+        }
+    }
+
+    if diags.has_errors() {
+        return Err(diags);
+    }
+
+    let mut global_attr = Vec::new();
+    let mut tydes = Vec::new();
+    let mut read_files = Vec::new();
+
+    if let Some(stdlib) = &options.stdlib {
+        let display_name = display_path(stdlib);
+
+        let content = match files.read(stdlib) {
+            Ok(content) => content,
+            Err(e) => {
+                diags.push(Diagnostic::without_span(format!(
+                    "无法打开指定的 stdlib 文件 {display_name}: {e}"
+                )));
+                return Err(diags);
+            }
+        };
+
+        source_map.add(&display_name, &content);
+        read_files.push(files.canonicalize(stdlib).unwrap_or_else(|_| stdlib.clone()));
+
+        match PestRSDLParser::parse(Rule::rsdl_program, &content) {
+            Ok(rsdl) => treeconv(&display_name, rsdl, &mut global_attr, &mut tydes),
+            Err(e) => {
+                diags.push(Diagnostic::from_pest(&display_name, &e));
+                return Err(diags);
+            }
+        }
+    } else {
+        source_map.add("(stdlib)", &options.prebuilt_stdlib);
+        match PestRSDLParser::parse(Rule::rsdl_program, &options.prebuilt_stdlib) {
+            Ok(rsdl) => treeconv("(stdlib)", rsdl, &mut global_attr, &mut tydes),
+            Err(e) => {
+                diags.push(Diagnostic::from_pest("(stdlib)", &e));
+                return Err(diags);
+            }
+        }
+    }
+
+    let mut preprocessed_files = HashSet::new();
+    let mut preprocess_queue: VecDeque<PathBuf> = VecDeque::new();
+    let mut parse_stack: Vec<(String, String, bool)> = Vec::new();
+
+    let path = match files.canonicalize(&options.input) {
+        Ok(path) => path,
+        Err(e) => {
+            diags.push(Diagnostic::without_span(format!(
+                "无法读取输入文件 {}: {}",
+                display_path(&options.input),
+                e
+            )));
+            return Err(diags);
+        }
+    };
+    let workdir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    preprocess_queue.push_back(path);
+
+    while let Some(path) = preprocess_queue.pop_front() {
+        let display_name = display_path(&path);
+
+        info!("预处理 {display_name}");
+
+        if preprocessed_files.contains(&path) {
+            warn!("文件 {} 已经被处理过，跳过", display_name);
+            continue;
+        }
+
+        let file_content = match files.read(&path) {
+            Ok(file_content) => file_content,
+            Err(e) => {
+                diags.push(Diagnostic::without_span(format!("无法读取文件 {display_name}: {e}")));
+                continue;
+            }
+        };
+
+        // 以 .asdl 为扩展名并且以 module 块开头的文件使用 Zephyr ASDL 兼容模式解析
+        let is_asdl = path.extension().is_some_and(|ext| ext == "asdl")
+            && is_asdl_module(&file_content);
+        let preprocessed = preprocess(&display_name, &file_content);
+        source_map.add(&display_name, file_content);
+        parse_stack.push((display_name, preprocessed.output_src, is_asdl));
+        read_files.push(path.clone());
+        preprocessed_files.insert(path);
+
+        for include in preprocessed.includes.into_iter().rev() {
+            match files.canonicalize(&workdir.join(&include)) {
+                Ok(include_path) => preprocess_queue.push_back(include_path),
+                Err(e) => diags.push(Diagnostic::without_span(format!(
+                    "无法解析引用的文件 {include}: {e}"
+                )))
+            }
+        }
+    }
+
+    for (display_name, src, is_asdl) in parse_stack.into_iter().rev() {
+        info!("解析 {display_name}");
+
+        if is_asdl {
+            match PestASDLParser::parse(asdl::Rule::asdl_program, &src) {
+                Ok(asdl) => asdlconv(&display_name, asdl, &mut tydes),
+                Err(e) => diags.push(Diagnostic::from_pest(&display_name, &e))
+            }
+        } else {
+            match PestRSDLParser::parse(Rule::rsdl_program, &src) {
+                Ok(rsdl) => treeconv(&display_name, rsdl, &mut global_attr, &mut tydes),
+                Err(e) => diags.push(Diagnostic::from_pest(&display_name, &e))
+            }
+        }
+    }
+
+    if diags.has_errors() {
+        diags.sort();
+        return Err(diags);
+    }
+
+    let mut resolve_ctx = ResolveContext::new(global_attr, &options.discriminant);
+
+    // 使用所有已加载的代码生成器（而不仅仅是本次的目标）声明的注解进行校验，
+    // 这样同一份源代码在只生成一部分目标时不会产生额外的警告
+    let mut known_attrs = CORE_ATTRS.to_vec();
+    for generator_factory in generators.iter() {
+        known_attrs.extend_from_slice(generator_factory.create().known_attrs());
+    }
+
+    resolve_ctx.min_resolv_global_attr(&known_attrs, &mut diags);
+
+    for tyde in tydes.iter() {
+        resolve_ctx.min_resolv(tyde, &mut diags);
+    }
+
+    for tyde in tydes.iter() {
+        resolve_ctx.min_resolv_attr(tyde, &known_attrs, &mut diags);
+        resolve_ctx.min_resolv_chk(tyde, &mut diags);
+    }
+
+    if diags.has_errors() {
+        diags.sort();
+        return Err(diags);
+    }
+
+    let mut outputs = Vec::new();
+    for generator_factory in target_generators {
+        info!("生成结构 ({})", generator_factory.lang_ident());
+
+        let mut generator = generator_factory.create();
+        if let Some(generator_options) = options.generator_options.get(generator_factory.lang_ident()) {
+            for (name, value) in generator_options.iter() {
+                if let Err(e) = generator.set_option(name, value) {
+                    diags.push(Diagnostic::without_span(format!(
+                        "{}: 无法设置选项 {}: {}",
+                        generator_factory.generator_name(),
+                        name,
+                        e
+                    )));
+                }
+            }
+        }
+
+        match codegen(
+            options.namespace.as_deref(),
+            &tydes,
+            &resolve_ctx,
+            generator.as_mut()
+        ) {
+            Ok(output) => outputs.push(TargetOutput {
+                lang_ident: generator_factory.lang_ident().to_string(),
+                code: output.to_string()
+            }),
+            Err(codegen_diags) => diags.extend(codegen_diags)
+        }
+    }
+
+    if diags.has_errors() {
+        diags.sort();
+        return Err(diags);
+    }
+
+    diags.sort();
+    Ok(CompileOutput {
+        outputs,
+        files: read_files,
+        warnings: diags
+    })
+}
+
+/// 生成用于诊断信息的文件名
+fn display_path(path: &Path) -> String {
+    let display_name = format!("{}", path.display());
+    #[cfg(windows)] let display_name = display_name.replace("\\\\?\\", "");
+    display_name
+}
+
+/// 单元测试使用的辅助函数，在内存中编译源代码
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::{compile, CompileOptions, MemoryFileProvider};
+    use crate::codegen::CodeGeneratorFactory;
+    use crate::codegen::rustgen::RustGeneratorFactory;
+    use crate::codegen::tsgen::TSInterfaceGeneratorFactory;
+    use crate::diagnostic::Diagnostics;
+
+    /// 编译一组文件，以第一个文件为根输入文件，为 `target` 生成代码
+    pub(crate) fn compile_files(files: &[(&str, &str)], target: &str) -> Result<String, Diagnostics> {
+        let mut provider = MemoryFileProvider::new();
+        for (path, src) in files {
+            provider.add(path, src);
+        }
+
+        let mut options = CompileOptions::new(files[0].0);
+        options.targets.push(target.to_string());
+        compile(options, &provider, &[&RustGeneratorFactory(), &TSInterfaceGeneratorFactory()])
+            .map(|mut output| output.outputs.remove(0).code)
+    }
+
+    /// 编译单个文件，编译失败时 panic
+    pub(crate) fn compile_ok(src: &str, target: &str) -> String {
+        compile_files(&[("test.rsdl", src)], target).unwrap_or_else(|diags| panic!("编译失败:\n{}", diags))
+    }
+
+    /// 编译单个文件，返回所有警告的位置和信息，编译失败时 panic
+    pub(crate) fn compile_warnings(src: &str) -> Vec<String> {
+        let mut provider = MemoryFileProvider::new();
+        provider.add("test.rsdl", src);
+
+        let generators: &[&dyn CodeGeneratorFactory] = &[&RustGeneratorFactory(), &TSInterfaceGeneratorFactory()];
+        compile(CompileOptions::new("test.rsdl"), &provider, generators)
+            .unwrap_or_else(|diags| panic!("编译失败:\n{}", diags))
+            .warnings
+            .iter()
+            .map(|diag| diag.message.clone())
+            .collect()
+    }
+
+    /// 编译单个文件，返回所有错误的位置和信息，编译成功时 panic
+    pub(crate) fn compile_err(src: &str, target: &str) -> Vec<String> {
+        match compile_files(&[("test.rsdl", src)], target) {
+            Ok(code) => panic!("编译应该失败，但是生成了:\n{}", code),
+            Err(diags) => diags
+                .iter()
+                .filter(|diag| diag.is_error())
+                .map(|diag| match &diag.span {
+                    Some(span) => format!("{}: {}", span, diag.message),
+                    None => diag.message.clone()
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compile, CompileOptions, MemoryFileProvider};
+    use super::testing::{compile_err, compile_files, compile_ok};
+    use crate::codegen::rustgen::RustGeneratorFactory;
+    use crate::codegen::tsgen::TSInterfaceGeneratorFactory;
+
+    #[test]
+    fn included_files_are_compiled() {
+        let code = compile_files(&[
+            ("dir/a.rsdl", "#include \"b.rsdl\"\nA(b: B)"),
+            ("dir/b.rsdl", "B(x: int)")
+        ], "rust").unwrap();
+        assert!(code.contains("pub struct A"));
+        assert!(code.contains("pub struct B"));
+    }
+
+    #[test]
+    fn errors_point_to_original_lines() {
+        let diags = compile_files(&[
+            ("a.rsdl", "#include \"b.rsdl\"\n\nA(x: int)"),
+            ("b.rsdl", "-- comment\nB(x: Unknown)")
+        ], "rust").unwrap_err();
+        let span = diags.iter().next().unwrap().span.clone().unwrap();
+        assert_eq!((span.file.as_str(), span.line), ("b.rsdl", 2));
+    }
+
+    #[test]
+    fn missing_include_is_an_error() {
+        let errors = compile_err("#include \"missing.rsdl\"\nA(x: int)", "rust");
+        assert!(errors[0].contains("missing.rsdl"), "{:?}", errors);
+    }
+
+    #[test]
+    fn commented_out_include_is_not_resolved() {
+        let code = compile_ok("{-\n#include \"missing.rsdl\"\n-}\nA(x: int)", "rust");
+        assert!(code.contains("pub struct A {"), "{}", code);
+    }
+
+    #[test]
+    fn parse_error_at_end_of_file_points_into_file() {
+        let errors = compile_err("A(x: int)\nB(\n", "rust");
+        assert!(errors[0].starts_with("test.rsdl:3:1"), "{:?}", errors);
+    }
+
+    #[test]
+    fn generates_every_target() {
+        let mut provider = MemoryFileProvider::new();
+        provider.add("test.rsdl", "A(x: int)");
+
+        let mut options = CompileOptions::new("test.rsdl");
+        options.targets = vec!["rust".to_string(), "typescript".to_string()];
+        let output = compile(options, &provider, &[&RustGeneratorFactory(), &TSInterfaceGeneratorFactory()]).unwrap();
+
+        assert_eq!(output.outputs.len(), 2);
+        assert_eq!(output.outputs[0].lang_ident, "rust");
+        assert!(output.outputs[0].code.contains("pub struct A {"), "{}", output.outputs[0].code);
+        assert_eq!(output.outputs[1].lang_ident, "typescript");
+        assert!(output.outputs[1].code.contains("export interface A {"), "{}", output.outputs[1].code);
+    }
+}
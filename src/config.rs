@@ -0,0 +1,210 @@
+//! 项目配置文件 `rsdl.toml`
+//!
+//! 项目配置文件列出了一个项目中所有的根输入文件、每个目标语言的输出文件以及编译选项，
+//! 这样 `rsdl build` 就可以一次性重新生成所有的代码。配置文件中的相对路径都相对于配置文件所在的目录。
+//!
+//! # 示例
+//!
+//! ```toml
+//! # 全局选项，可以被每个 schema 中的同名选项覆盖
+//! stdlib = "schema/stdlib.rsdl"
+//! discriminant = "$kind"
+//!
+//! # 代码生成器的选项，以语言标识符为键
+//! [generators.rust]
+//! header = "use serde::{Serialize, Deserialize};"
+//!
+//! [[schemas]]
+//! input = "schema/ast.rsdl"
+//! namespace = "ast"
+//! targets = { rust = "src/ast.rs", typescript = "web/src/ast.ts" }
+//!
+//! [[schemas]]
+//! input = "schema/ir.rsdl"
+//! discriminant = "kind"
+//! targets = { rust = "src/ir.rs" }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use toml::{Table, Value};
+
+use crate::compiler::CompileOptions;
+use crate::diagnostic::{Diagnostic, SourceMap, Span};
+
+/// 默认的项目配置文件名
+pub const DEFAULT_CONFIG_FILE: &str = "rsdl.toml";
+
+/// 项目配置
+#[derive(Debug, Clone, Default)]
+pub struct ProjectConfig {
+    /// 自定义的标准库文件
+    pub stdlib: Option<PathBuf>,
+    /// 默认的名称空间
+    pub namespace: Option<String>,
+    /// 默认的和类型 `discriminant` 名称
+    pub discriminant: Option<String>,
+    /// 每个代码生成器的选项，以语言标识符为键
+    pub generator_options: HashMap<String, BTreeMap<String, String>>,
+    /// 所有的根输入文件
+    pub schemas: Vec<SchemaConfig>
+}
+
+/// 项目配置中的一个根输入文件
+#[derive(Debug, Clone, Default)]
+pub struct SchemaConfig {
+    /// 根输入文件
+    pub input: PathBuf,
+    /// 生成代码所在的名称空间，覆盖全局设置
+    pub namespace: Option<String>,
+    /// 和类型的 `discriminant` 名称，覆盖全局设置
+    pub discriminant: Option<String>,
+    /// 每个目标语言的输出文件
+    pub targets: Vec<(String, PathBuf)>
+}
+
+impl ProjectConfig {
+    /// 读取并解析项目配置文件
+    ///
+    /// 配置文件的内容会被加入 `source_map`，以便输出带有代码片段的错误信息
+    pub fn load(path: &Path, source_map: &mut SourceMap) -> Result<Self, Diagnostic> {
+        let display_name = format!("{}", path.display());
+        let src = std::fs::read_to_string(path).map_err(|e| Diagnostic::without_span(format!(
+            "无法读取配置文件 {}: {}",
+            display_name,
+            e
+        )))?;
+        source_map.add(&display_name, &src);
+
+        let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Self::parse(&display_name, &src, &base_dir)
+    }
+
+    /// 解析项目配置文件的内容，配置中的相对路径会被解析为相对于 `base_dir` 的路径
+    pub fn parse(file_name: &str, src: &str, base_dir: &Path) -> Result<Self, Diagnostic> {
+        let table = src.parse::<Table>().map_err(|e| {
+            let message = format!("配置文件语法错误: {}", e.message());
+            match e.span() {
+                Some(range) => {
+                    let span = span_from_offset(file_name, src, range.start, range.len());
+                    Diagnostic::new(span, message)
+                },
+                None => Diagnostic::without_span(format!("{}: {}", file_name, message))
+            }
+        })?;
+
+        let error = |message: String| Diagnostic::without_span(format!("{}: {}", file_name, message));
+
+        let mut config = ProjectConfig::default();
+        for (key, value) in table.iter() {
+            match key.as_str() {
+                "stdlib" => config.stdlib = Some(base_dir.join(expect_str(key, value).map_err(error)?)),
+                "namespace" => config.namespace = Some(expect_str(key, value).map_err(error)?.to_string()),
+                "discriminant" => config.discriminant = Some(expect_str(key, value).map_err(error)?.to_string()),
+                "generators" => {
+                    for (lang_ident, options) in expect_table(key, value).map_err(error)?.iter() {
+                        let key = format!("generators.{}", lang_ident);
+                        let mut generator_options = BTreeMap::new();
+                        for (name, value) in expect_table(&key, options).map_err(error)?.iter() {
+                            let value = match value {
+                                Value::String(s) => s.clone(),
+                                Value::Integer(_) | Value::Float(_) | Value::Boolean(_) => value.to_string(),
+                                _ => return Err(error(format!(
+                                    "选项 {}.{} 的值应该是字符串、数字或者布尔值",
+                                    key,
+                                    name
+                                )))
+                            };
+                            generator_options.insert(name.clone(), value);
+                        }
+                        config.generator_options.insert(lang_ident.clone(), generator_options);
+                    }
+                },
+                "schemas" => {
+                    let Value::Array(schemas) = value else {
+                        return Err(error("配置项 schemas 应该是表的数组".to_string()));
+                    };
+                    for (idx, schema) in schemas.iter().enumerate() {
+                        let key = format!("schemas[{}]", idx);
+                        let schema = expect_table(&key, schema).map_err(error)?;
+                        config.schemas.push(parse_schema(&key, schema, base_dir).map_err(error)?);
+                    }
+                },
+                _ => return Err(error(format!("未知的配置项 {}", key)))
+            }
+        }
+
+        if config.schemas.is_empty() {
+            return Err(error("配置文件中没有任何 schema".to_string()));
+        }
+
+        Ok(config)
+    }
+}
+
+impl SchemaConfig {
+    /// 根据项目配置生成这个根输入文件的编译选项
+    ///
+    /// 返回的编译选项使用 [`crate::driver::REFERENTIAL_STDLIB`] 作为预编译的标准库，
+    /// 调用者可以在需要时替换
+    pub fn compile_options(&self, project: &ProjectConfig) -> CompileOptions {
+        let mut options = CompileOptions::new(&self.input);
+        options.targets = self.targets.iter().map(|(lang_ident, _)| lang_ident.clone()).collect();
+        options.namespace = self.namespace.clone().or_else(|| project.namespace.clone());
+        if let Some(discriminant) = self.discriminant.as_ref().or(project.discriminant.as_ref()) {
+            options.discriminant = discriminant.clone();
+        }
+        options.stdlib = project.stdlib.clone();
+        options.generator_options = project.generator_options.clone();
+        options
+    }
+}
+
+fn parse_schema(key: &str, schema: &Table, base_dir: &Path) -> Result<SchemaConfig, String> {
+    let mut input = None;
+    let mut config = SchemaConfig::default();
+
+    for (name, value) in schema.iter() {
+        let item_key = format!("{}.{}", key, name);
+        match name.as_str() {
+            "input" => input = Some(base_dir.join(expect_str(&item_key, value)?)),
+            "namespace" => config.namespace = Some(expect_str(&item_key, value)?.to_string()),
+            "discriminant" => config.discriminant = Some(expect_str(&item_key, value)?.to_string()),
+            "targets" => {
+                for (lang_ident, output) in expect_table(&item_key, value)?.iter() {
+                    let output = expect_str(&format!("{}.{}", item_key, lang_ident), output)?;
+                    config.targets.push((lang_ident.clone(), base_dir.join(output)));
+                }
+            },
+            _ => return Err(format!("未知的配置项 {}", item_key))
+        }
+    }
+
+    config.input = input.ok_or_else(|| format!("{} 缺少配置项 input", key))?;
+    if config.targets.is_empty() {
+        return Err(format!("{} 没有指定任何输出目标", key));
+    }
+
+    Ok(config)
+}
+
+fn expect_str<'a>(key: &str, value: &'a Value) -> Result<&'a str, String> {
+    value.as_str().ok_or_else(|| format!("配置项 {} 的值应该是字符串", key))
+}
+
+fn expect_table<'a>(key: &str, value: &'a Value) -> Result<&'a Table, String> {
+    value.as_table().ok_or_else(|| format!("配置项 {} 的值应该是表", key))
+}
+
+/// 将字节偏移量转换为源代码位置
+fn span_from_offset(file_name: &str, src: &str, offset: usize, len: usize) -> Span {
+    let offset = offset.min(src.len());
+    let before = &src[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+    let col = before[line_start..].chars().count() + 1;
+    let len = src.get(offset..offset + len).map(|s| s.chars().count()).unwrap_or(1);
+
+    Span { file: file_name.to_string(), line, col, len }
+}
@@ -0,0 +1,331 @@
+//! `rsdl` 的诊断信息：源代码位置、源代码表以及错误信息的格式化输出
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use pest::RuleType;
+use pest::error::LineColLocation;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+/// 源代码中的一段位置
+///
+/// 行号和列号都从 1 开始计数，列号和长度都以字符（而非字节）为单位
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Span {
+    /// 所在的文件
+    pub file: String,
+    /// 起始行号
+    pub line: usize,
+    /// 起始列号
+    pub col: usize,
+    /// 长度
+    pub len: usize
+}
+
+impl Span {
+    /// 从 Pest 的 `Span` 创建一个位置
+    pub fn from_pest(file_name: &str, span: pest::Span) -> Self {
+        let (line, col) = span.start_pos().line_col();
+        Self {
+            file: file_name.to_string(),
+            line,
+            col,
+            len: span.as_str().chars().count()
+        }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.col)
+    }
+}
+
+/// 源代码表
+///
+/// 记录每个文件的源代码，用于在诊断信息中打印出错位置附近的代码片段
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    files: HashMap<String, String>
+}
+
+impl SourceMap {
+    /// 创建一个空的源代码表
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 向源代码表中添加一个文件
+    pub fn add(&mut self, file_name: impl ToString, src: impl ToString) {
+        self.files.insert(file_name.to_string(), src.to_string());
+    }
+
+    /// 获取一个文件的源代码
+    pub fn get(&self, file_name: &str) -> Option<&str> {
+        self.files.get(file_name).map(String::as_str)
+    }
+
+    /// 遍历源代码表中所有的文件名
+    pub fn file_names(&self) -> impl Iterator<Item = &str> {
+        self.files.keys().map(String::as_str)
+    }
+
+    /// 生成 `span` 所在行的代码片段，并用 `^` 标出 `span` 的范围
+    ///
+    /// 如果 `span` 跨越多行，则只标出第一行的部分
+    ///
+    /// # 示例
+    ///
+    /// ```text
+    ///    |
+    /// 12 |   CallExpr(fn: Expr, args: list<Expr>)
+    ///    |            ^^
+    /// ```
+    pub fn excerpt(&self, span: &Span) -> Option<String> {
+        let src = self.get(&span.file)?;
+        let line = src.split('\n').nth(span.line.checked_sub(1)?)?;
+        let line = line.trim_end_matches('\r');
+
+        let line_chars = line.chars().count();
+        let col = span.col.max(1).min(line_chars + 1);
+        let caret_len = span.len.min(line_chars + 1 - col).max(1);
+
+        let prefix = line
+            .chars()
+            .take(col - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+
+        let lineno = span.line.to_string();
+        let gutter = " ".repeat(lineno.len());
+
+        Some(format!(
+            "{gutter} |\n{lineno} | {line}\n{gutter} | {prefix}{}",
+            "^".repeat(caret_len)
+        ))
+    }
+}
+
+/// 诊断信息的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    /// 错误，会导致编译失败
+    Error,
+    /// 警告，不会导致编译失败
+    Warning
+}
+
+/// 一条诊断信息
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// 诊断信息对应的源代码位置
+    pub span: Option<Span>,
+    /// 诊断信息的严重程度
+    pub severity: Severity,
+    /// 诊断信息的内容
+    pub message: String
+}
+
+impl Diagnostic {
+    /// 创建一条带有源代码位置的错误信息
+    pub fn new(span: Span, message: impl ToString) -> Self {
+        Self {
+            span: Some(span),
+            severity: Severity::Error,
+            message: message.to_string()
+        }
+    }
+
+    /// 创建一条没有源代码位置的错误信息
+    pub fn without_span(message: impl ToString) -> Self {
+        Self {
+            span: None,
+            severity: Severity::Error,
+            message: message.to_string()
+        }
+    }
+
+    /// 创建一条带有源代码位置的警告信息
+    pub fn warning(span: Span, message: impl ToString) -> Self {
+        Self {
+            span: Some(span),
+            severity: Severity::Warning,
+            message: message.to_string()
+        }
+    }
+
+    /// 这条诊断信息是否是错误
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// 将 Pest 报告的语法错误转换为诊断信息
+    ///
+    /// 由于预处理器保证了行号不变，语法错误的位置可以直接对应到原始文件中
+    pub fn from_pest<R: RuleType>(file_name: &str, err: &pest::error::Error<R>) -> Self {
+        let (line, col, len) = match err.line_col {
+            LineColLocation::Pos((line, col)) => (line, col, 1),
+            LineColLocation::Span((line, col), (end_line, end_col)) => {
+                if line == end_line {
+                    (line, col, end_col.saturating_sub(col).max(1))
+                } else {
+                    (line, col, 1)
+                }
+            }
+        };
+
+        Self::new(
+            Span { file: file_name.to_string(), line, col, len },
+            format!("语法错误: {}", err.variant.message())
+        )
+    }
+
+    /// 将诊断信息格式化为 `file:line:col: message` 的形式，并附上出错位置的代码片段
+    ///
+    /// 警告信息的内容之前会加上 `警告: `
+    pub fn render(&self, source_map: &SourceMap) -> String {
+        let Some(span) = &self.span else {
+            return self.to_string();
+        };
+
+        match source_map.excerpt(span) {
+            Some(excerpt) => format!("{}\n{}", self, excerpt),
+            None => self.to_string()
+        }
+    }
+}
+
+/// 诊断信息收集器
+///
+/// 编译过程中的各个阶段会将遇到的所有错误都加入收集器，而不是在遇到第一个错误时就停止，
+/// 这样用户可以在一次运行中看到所有的错误
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    items: Vec<Diagnostic>
+}
+
+impl Diagnostics {
+    /// 创建一个空的诊断信息收集器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加一条诊断信息
+    pub fn push(&mut self, diag: Diagnostic) {
+        self.items.push(diag);
+    }
+
+    /// 收集器中是否没有任何诊断信息
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// 收集器中诊断信息的数量
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// 收集器中是否有错误（而不仅仅是警告）
+    pub fn has_errors(&self) -> bool {
+        self.items.iter().any(Diagnostic::is_error)
+    }
+
+    /// 收集器中错误的数量
+    pub fn error_count(&self) -> usize {
+        self.items.iter().filter(|diag| diag.is_error()).count()
+    }
+
+    /// 遍历收集器中的诊断信息
+    pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+        self.items.iter()
+    }
+
+    /// 按照源代码位置（文件、行号、列号）对诊断信息进行排序
+    ///
+    /// 没有位置的诊断信息会排在最后
+    pub fn sort(&mut self) {
+        self.items.sort_by(|a, b| {
+            let key = |diag: &Diagnostic| diag.span.as_ref().map(|span| {
+                (span.file.clone(), span.line, span.col)
+            });
+
+            match (key(a), key(b)) {
+                (Some(a), Some(b)) => a.cmp(&b),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal
+            }
+        });
+    }
+
+    /// 将所有的诊断信息格式化输出，每条诊断信息之间以空行分隔
+    pub fn render(&self, source_map: &SourceMap) -> String {
+        self.items
+            .iter()
+            .map(|diag| diag.render(source_map))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+impl From<Diagnostic> for Diagnostics {
+    fn from(diag: Diagnostic) -> Self {
+        Self { items: vec![diag] }
+    }
+}
+
+impl Extend<Diagnostic> for Diagnostics {
+    fn extend<T: IntoIterator<Item = Diagnostic>>(&mut self, iter: T) {
+        self.items.extend(iter);
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Diagnostics {
+    type Item = &'a Diagnostic;
+    type IntoIter = std::slice::Iter<'a, Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (idx, diag) in self.items.iter().enumerate() {
+            if idx != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", diag)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for Diagnostics {}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(span) = &self.span {
+            write!(f, "{}: ", span)?;
+        }
+        if self.severity == Severity::Warning {
+            write!(f, "警告: ")?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for Diagnostic {}
@@ -1,58 +1,91 @@
 //! `rsdl` 应用程序的实际入口点
 
-use std::collections::{HashSet, VecDeque};
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use pest::Parser;
+use similar::TextDiff;
 use structopt::StructOpt;
 use tracing::{error, info, warn};
 
-use crate::codegen::{codegen, CodeGeneratorFactory};
-use crate::min_resolv::ResolveContext;
-use crate::parser::pest_parser::{PestRSDLParser, Rule};
-use crate::parser::treeconv::treeconv;
-use crate::preprocess::preprocess;
+use crate::codegen::CodeGeneratorFactory;
+use crate::compiler::{compile_with_source_map, CompileOptions, FsFileProvider};
+use crate::config::{ProjectConfig, DEFAULT_CONFIG_FILE};
+use crate::diagnostic::{Diagnostics, SourceMap};
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "rsdl", about = "RSDL 优化编译器")]
 struct Options {
+    #[structopt(subcommand)]
+    command: Option<Command>,
+
     // -i, --input FILENAME
     #[structopt(short, long, parse(from_os_str))]
-    input: PathBuf,
+    input: Option<PathBuf>,
 
     // -o, --output FILENAME
     #[structopt(short, long, parse(from_os_str))]
-    output: PathBuf,
+    output: Option<PathBuf>,
 
     // -t, --mode MODE
     #[structopt(short = "t", long = "mode")]
-    mode: String,
+    mode: Option<String>,
+
+    // --target MODE=FILENAME ...
+    #[structopt(long = "target", number_of_values = 1, parse(try_from_str = parse_target))]
+    targets: Vec<(String, PathBuf)>,
 
     // --namespace NAMESPACE
-    #[structopt(long)]
+    #[structopt(long, global = true)]
     namespace: Option<String>,
 
     // --stdlib STDLIB
-    #[structopt(long, parse(from_os_str))]
+    #[structopt(long, parse(from_os_str), global = true)]
     stdlib: Option<PathBuf>,
 
     // -d, --discriminant DISCRIMINANT
-    #[structopt(short = "d", long = "discriminant", default_value = "$kind")]
-    discriminant: String,
+    #[structopt(short = "d", long = "discriminant", global = true)]
+    discriminant: Option<String>,
+
+    // --check
+    #[structopt(long, global = true)]
+    check: bool,
+
+    // --watch
+    #[structopt(long, global = true)]
+    watch: bool,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// 根据项目配置文件生成所有的代码
+    Build {
+        // -c, --config FILENAME
+        #[structopt(short, long, parse(from_os_str), default_value = DEFAULT_CONFIG_FILE)]
+        config: PathBuf
+    }
 }
 
+/// 一次编译任务：编译选项以及每个目标语言的输出文件
+type Job = (CompileOptions, Vec<(String, PathBuf)>);
+
+/// `--watch` 模式下检查文件变化的间隔
+const WATCH_INTERVAL: Duration = Duration::from_millis(300);
+
 /// 启动 RSDL 编译流程
 ///
 /// 这个函数会根据 `RUST_LOG` 设置日志级别、初始化日志系统、
 /// 加载预编译的标准库、解析命令行参数、加载代码生成器，
-/// 解析用户输入，调用代码生成器生成代码，最后将生成的代码写入输出文件
+/// 解析用户输入，调用代码生成器生成代码，最后将生成的代码写入输出文件。
+/// 编译失败时，这个函数会输出所有的错误信息，并以非零状态码退出进程
 ///
 /// 一般而言，下游程序应该直接在 `main` 函数中调用这个函数，并在
 /// `prebuilt_stdlib` 参数中传入 [`crate::driver::REFERENTIAL_STDLIB`]
 /// 或者自定义的标准库，`build_info` 参数中传入额外的构建信息，
 /// `generators` 参数中传入所有要使用的代码生成器。
 ///
+/// 如果希望将编译器嵌入到其他程序中，请使用 [`crate::compile`]
+///
 /// # 参数
 /// - `prebuilt_stdlib` - 预编译的标准库，在编译输入文件之前加载
 /// - `build_info` - 额外的构建信息，例如版权等信息，会在启动时打印
@@ -62,21 +95,16 @@ pub fn application_start(
     build_info: Option<&str>,
     generators: &[&dyn CodeGeneratorFactory]
 ) {
-    if let Err(_) = env::var("RUST_LOG") {
+    if env::var("RUST_LOG").is_err() {
         env::set_var("RUST_LOG", "info");
     };
 
-    tracing_subscriber::fmt::init();
-
-    let lang_idents = generators
-        .iter()
-        .map(|generator| generator.lang_ident())
-        .collect::<HashSet<&'static str>>();
+    let _ = tracing_subscriber::fmt::try_init();
 
     if let Some(build_info) = build_info {
         info!("RSDL 优化编译器 - 非公开构建");
         info!("额外构建信息:");
-        for line in build_info.split("\n") {
+        for line in build_info.split('\n') {
             info!("{}", line);
         }
 
@@ -92,131 +120,275 @@ pub fn application_start(
 
     let opt = Options::from_args();
 
-    if !lang_idents.contains(opt.mode.as_str()) {
-        error!("不支持的输出模式 {}", opt.mode);
-        return;
+    if opt.watch && opt.check {
+        error!("--watch 不能与 --check 同时使用");
+        std::process::exit(1);
     }
 
-    let mut global_attr = Vec::new();
-    let mut tydes = Vec::new();
-    if let Some(stdlib) = opt.stdlib {
-        let display_name = format!("{}", stdlib.display());
+    if opt.watch {
+        watch(&opt, prebuilt_stdlib, generators);
+    }
 
-        let Ok(content) = std::fs::read_to_string(&stdlib) else {
-            error!("无法打开指定的 stdlib 文件 {display_name}");
-            return;
-        };
+    let jobs = match load_jobs(&opt, prebuilt_stdlib) {
+        Ok(jobs) => jobs,
+        Err((diags, source_map)) => report_and_exit(diags, &source_map)
+    };
 
-        let rsdl = match PestRSDLParser::parse(Rule::rsdl_program, &content) {
-            Ok(rsdl) => rsdl,
-            Err(e) => {
-                error!("解析 stdlib 文件 {display_name} 失败:\n{e}");
-                return;
-            }
-        };
+    let result = run_jobs(&opt, jobs, generators);
 
-        treeconv(&display_name, rsdl, &mut global_attr, &mut tydes);
-    } else {
-        let rsdl = PestRSDLParser::parse(Rule::rsdl_program, prebuilt_stdlib).unwrap();
-        treeconv("(stdlib)", rsdl, &mut global_attr, &mut tydes);
+    if result.error_count != 0 {
+        error!("编译失败，共 {} 个错误", result.error_count);
+        std::process::exit(1);
     }
 
-    let mut preprocessed_files = HashSet::new();
-    let mut preprocess_queue: VecDeque<PathBuf> = VecDeque::new();
-    let mut parse_stack: Vec<(PathBuf, String)> = Vec::new();
+    if result.outdated_count != 0 {
+        error!("{} 个输出文件不是最新的，请重新生成", result.outdated_count);
+        std::process::exit(1);
+    }
+}
 
-    let path = opt.input.canonicalize().unwrap();
-    let workdir = path.parent().unwrap().canonicalize().unwrap();
+/// 执行所有编译任务的结果
+struct RunResult {
+    /// 错误的数量
+    error_count: usize,
+    /// `--check` 模式下不是最新的输出文件的数量
+    outdated_count: usize,
+    /// 编译过程中读取的所有文件
+    files: Vec<PathBuf>
+}
 
-    preprocess_queue.push_back(path);
+/// 根据命令行参数或者项目配置文件创建所有的编译任务
+fn load_jobs(opt: &Options, prebuilt_stdlib: &str) -> Result<Vec<Job>, (Diagnostics, SourceMap)> {
+    let mut jobs = match &opt.command {
+        Some(Command::Build { config }) => {
+            let mut source_map = SourceMap::new();
+            match ProjectConfig::load(config, &mut source_map) {
+                Ok(config) => config.schemas
+                    .iter()
+                    .map(|schema| (schema.compile_options(&config), schema.targets.clone()))
+                    .collect(),
+                Err(diag) => return Err((diag.into(), source_map))
+            }
+        },
+        None => vec![cli_job(opt)]
+    };
 
-    while !preprocess_queue.is_empty() {
-        let path = preprocess_queue.pop_front().unwrap();
-        let display_name = format!("{}", path.display());
-        #[cfg(windows)] let display_name = display_name.replace("\\\\?\\", "");
+    // 命令行参数覆盖配置文件中的设置
+    for (options, _) in jobs.iter_mut() {
+        options.prebuilt_stdlib = prebuilt_stdlib.to_string();
+        if let Some(namespace) = &opt.namespace {
+            options.namespace = Some(namespace.clone());
+        }
+        if let Some(stdlib) = &opt.stdlib {
+            options.stdlib = Some(stdlib.clone());
+        }
+        if let Some(discriminant) = &opt.discriminant {
+            options.discriminant = discriminant.clone();
+        }
+    }
 
-        info!("预处理 {display_name}");
+    Ok(jobs)
+}
 
-        if preprocessed_files.contains(&path) {
-            warn!("文件 {} 已经被处理过，跳过", path.display());
-            continue;
-        }
+/// 执行所有的编译任务，输出诊断信息，并写入（或者在 `--check` 模式下检查）输出文件
+fn run_jobs(opt: &Options, jobs: Vec<Job>, generators: &[&dyn CodeGeneratorFactory]) -> RunResult {
+    let mut result = RunResult {
+        error_count: 0,
+        outdated_count: 0,
+        files: Vec::new()
+    };
 
-        let Ok(file_content) = std::fs::read_to_string(&path) else {
-            error!("无法读取文件 {display_name}");
-            return;
+    for (options, targets) in jobs {
+        let input = options.input.clone();
+        let stdlib = options.stdlib.clone();
+
+        let mut source_map = SourceMap::new();
+        let output = match compile_with_source_map(options, &FsFileProvider, generators, &mut source_map) {
+            Ok(output) => output,
+            Err(diags) => {
+                result.error_count += report(diags, &source_map);
+
+                // 编译失败时没有完整的文件列表，退而使用已经读取过的文件
+                result.files.push(input);
+                result.files.extend(stdlib);
+                result.files.extend(
+                    source_map.file_names()
+                        .map(PathBuf::from)
+                        .filter(|path| path.is_file())
+                );
+                continue;
+            }
         };
 
-        let preprocessed = preprocess(&display_name, &file_content);
-        parse_stack.push((path.clone(), preprocessed.output_src));
-        preprocessed_files.insert(path.clone());
+        report(output.warnings, &source_map);
+        result.files.extend(output.files);
 
-        for include in preprocessed.includes.into_iter().rev() {
-            let Ok(include_path) = workdir.join(&include).canonicalize() else {
-                error!("无法解析引用的文件 {include}");
-                return;
-            };
-            preprocess_queue.push_back(include_path);
+        for (target_output, (_, output_path)) in output.outputs.into_iter().zip(targets.iter()) {
+            let display_name = format!("{}", output_path.display());
+            #[cfg(windows)] let display_name = display_name.replace("\\\\?\\", "");
+
+            if opt.check {
+                if !check_output(output_path, &display_name, &target_output.code) {
+                    result.outdated_count += 1;
+                }
+                continue;
+            }
+
+            info!("输出文件 {} ({})", display_name, target_output.lang_ident);
+            if let Err(e) = std::fs::write(output_path, target_output.code) {
+                error!("无法写入输出文件 {}: {}", display_name, e);
+                result.error_count += 1;
+            }
         }
     }
 
-    for (path, src) in parse_stack.into_iter().rev() {
-        let display_name = format!("{}", path.display());
-        #[cfg(windows)] let display_name = display_name.replace("\\\\?\\", "");
+    result
+}
 
-        info!("解析 {display_name}");
+/// 持续监视输入文件，在文件发生变化时重新生成代码
+///
+/// 被监视的文件包括根输入文件、所有 `#include` 的文件、自定义的标准库以及项目配置文件。
+/// 每次生成之后都会重新计算被监视的文件，这样新引用的文件也会被监视
+fn watch(opt: &Options, prebuilt_stdlib: &str, generators: &[&dyn CodeGeneratorFactory]) -> ! {
+    loop {
+        let mut watched_files = Vec::new();
+        if let Some(Command::Build { config }) = &opt.command {
+            watched_files.push(config.clone());
+        }
 
-        let rsdl = match PestRSDLParser::parse(Rule::rsdl_program, &src) {
-            Ok(rsdl) => rsdl,
-            Err(e) => {
-                error!("解析 RSDL 文件 {display_name} 失败:\n{e}");
-                return;
+        match load_jobs(opt, prebuilt_stdlib) {
+            Ok(jobs) => {
+                let result = run_jobs(opt, jobs, generators);
+                if result.error_count != 0 {
+                    error!("编译失败，共 {} 个错误", result.error_count);
+                } else {
+                    info!("编译完成");
+                }
+                watched_files.extend(result.files);
+            },
+            Err((diags, source_map)) => {
+                let error_count = report(diags, &source_map);
+                error!("编译失败，共 {} 个错误", error_count);
             }
-        };
+        }
 
-        treeconv(&display_name, rsdl, &mut global_attr, &mut tydes);
-    }
+        watched_files.sort();
+        watched_files.dedup();
 
-    let mut resolve_ctx = ResolveContext::new(global_attr, opt.discriminant);
+        info!("正在监视 {} 个文件的变化", watched_files.len());
+        wait_for_change(&watched_files);
+    }
+}
 
-    for tyde in tydes.iter() {
-        if resolve_ctx.min_resolv(tyde).is_err() {
+/// 轮询文件的修改时间，直到任意一个文件被修改、创建或者删除
+fn wait_for_change(files: &[PathBuf]) {
+    let modified_time = |path: &PathBuf| std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+
+    let snapshot = files.iter().map(modified_time).collect::<Vec<_>>();
+    loop {
+        std::thread::sleep(WATCH_INTERVAL);
+
+        if let Some(path) = files.iter()
+            .zip(snapshot.iter())
+            .find(|(path, time)| modified_time(path) != **time)
+            .map(|(path, _)| path)
+        {
+            info!("文件 {} 发生了变化，重新生成", path.display());
             return;
         }
     }
+}
 
-    for tyde in tydes.iter() {
-        if resolve_ctx.min_resolv_chk(tyde).is_err() {
-            return;
+/// 检查输出文件的内容是否与生成的代码一致，不一致时输出 unified diff
+fn check_output(output_path: &Path, display_name: &str, code: &str) -> bool {
+    let existing = match std::fs::read_to_string(output_path) {
+        Ok(existing) => existing,
+        Err(e) => {
+            error!("无法读取输出文件 {}: {}", display_name, e);
+            return false;
+        }
+    };
+
+    if existing == code {
+        info!("输出文件 {} 是最新的", display_name);
+        return true;
+    }
+
+    error!("输出文件 {} 不是最新的", display_name);
+    print!(
+        "{}",
+        TextDiff::from_lines(existing.as_str(), code)
+            .unified_diff()
+            .header(display_name, &format!("{} (生成)", display_name))
+    );
+    false
+}
+
+/// 根据命令行参数创建编译任务
+fn cli_job(opt: &Options) -> Job {
+    let Some(input) = &opt.input else {
+        error!("没有指定输入文件，请使用 -i FILENAME，或者使用 rsdl build 根据项目配置文件生成代码");
+        std::process::exit(1);
+    };
+
+    let mut targets = opt.targets.clone();
+    match (&opt.mode, &opt.output) {
+        (Some(mode), Some(output)) => targets.insert(0, (mode.clone(), output.clone())),
+        (None, None) => {},
+        (Some(_), None) => {
+            error!("使用 -t/--mode 时必须同时使用 -o/--output 指定输出文件");
+            std::process::exit(1);
+        },
+        (None, Some(_)) => {
+            error!("使用 -o/--output 时必须同时使用 -t/--mode 指定输出模式");
+            std::process::exit(1);
         }
     }
 
-    let generator_factory = generators
-        .iter()
-        .find(|generator| generator.lang_ident() == opt.mode.as_str())
-        .unwrap();
-
-    info!("生成结构");
-    let mut generator = generator_factory.create();
-    if let Ok(output) = codegen(
-        opt.namespace.as_ref().map(|s| s.as_str()),
-        &tydes,
-        &resolve_ctx,
-        generator.as_mut()
-    ) {
-        let output = output.to_string();
-        let display_name = format!("{}", opt.output.display());
-        #[cfg(windows)] let display_name = display_name.replace("\\\\?\\", "");
-
-        info!("输出文件 {}", display_name);
-        if let Err(e) = std::fs::write(&opt.output, output) {
-            error!("无法写入输出文件 {}: {}", display_name, e);
+    if targets.is_empty() {
+        error!("没有指定任何输出目标，请使用 --target MODE=FILENAME 或者 -t MODE -o FILENAME");
+        std::process::exit(1);
+    }
+
+    let mut options = CompileOptions::new(input);
+    options.targets = targets.iter().map(|(mode, _)| mode.clone()).collect();
+    (options, targets)
+}
+
+/// 解析 `--target MODE=FILENAME` 形式的输出目标
+fn parse_target(src: &str) -> Result<(String, PathBuf), String> {
+    match src.split_once('=') {
+        Some((mode, output)) if !mode.is_empty() && !output.is_empty() => {
+            Ok((mode.to_string(), PathBuf::from(output)))
+        },
+        _ => Err(format!("无效的输出目标 {}，应为 MODE=FILENAME 的形式", src))
+    }
+}
+
+/// 按照源代码位置排序并输出所有的诊断信息，返回错误的数量
+fn report(mut diags: Diagnostics, source_map: &SourceMap) -> usize {
+    diags.sort();
+    for diag in &diags {
+        if diag.is_error() {
+            error!("{}", diag.render(source_map));
+        } else {
+            warn!("{}", diag.render(source_map));
         }
     }
+    diags.error_count()
+}
+
+/// 输出所有的诊断信息，然后以非零状态码退出
+fn report_and_exit(diags: Diagnostics, source_map: &SourceMap) -> ! {
+    let error_count = report(diags, source_map);
+    error!("编译失败，共 {} 个错误", error_count);
+    std::process::exit(1)
 }
 
 /// `rsdl` crate 自带的，供参考的标准库
 ///
 /// 该标准库包含了一些常用的类型定义，以及一些常用的函数。可以支持 `rsdl` crate 自带的代码生成器。
 /// 如果下游希望实现新的代码生成器，往往需要编写一个新的标准库。
-pub const REFERENTIAL_STDLIB: &'static str = include_str!("stdlib.rsdl");
+pub const REFERENTIAL_STDLIB: &str = include_str!("stdlib.rsdl");
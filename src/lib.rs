@@ -5,9 +5,20 @@
 //! 和代码生成器工厂（[`crate::codegen::CodeGeneratorFactory`]），
 //! 并编写与之配套的标准库，然后在自己程序的入口点（`main` 函数）调用
 //! [`crate::driver::application_start`] 即可。
+//!
+//! 如果希望在自己的工具或者测试中嵌入 RSDL 编译器，请使用 [`crate::compile`]，
+//! 它不会读取命令行参数、初始化日志系统或者写入文件。
 
+pub mod attr_spec;
+pub mod build;
 pub mod codegen;
+pub mod compiler;
+pub mod config;
+pub mod diagnostic;
 pub mod driver;
 pub mod min_resolv;
+pub mod naming;
 pub mod parser;
 pub mod preprocess;
+
+pub use compiler::compile;
@@ -2,9 +2,100 @@
 
 use std::collections::HashMap;
 
-use tracing::error;
+use smallvec::SmallVec;
 
-use crate::parser::hir::{check_inline, RSDLType, TypeDef, TypeDefInner, AttrItem};
+use crate::attr_spec::{check_attrs, AttrPosition, AttrSpec};
+use crate::diagnostic::{Diagnostic, Diagnostics, Span};
+use crate::naming::{check_rename_attrs, rename_rule, wire_name, RenameRule};
+use crate::parser::hir::{
+    check_inline,
+    Attr,
+    used_type_params,
+    AttrQuery,
+    Field,
+    Literal,
+    RSDLType,
+    SumType,
+    TypeDef,
+    TypeDefInner
+};
+
+/// 使用注解声明校验一组注解，并检查其中的命名注解的参数
+fn chk_attrs(attr_list: &[Attr], position: AttrPosition, known_attrs: &[AttrSpec], diags: &mut Diagnostics) {
+    check_attrs(attr_list, position, known_attrs, diags);
+    check_rename_attrs(attr_list, diags);
+}
+
+/// 映射的键类型和集合的元素类型需要满足的要求，用于错误信息
+const KEY_TYPE_REQUIREMENT: &str =
+    "只能是 str、int、只包含标量变体的和类型、native 类型或者它们的别名";
+
+/// 一个已知的类型
+#[derive(Debug, Clone)]
+pub struct KnownType {
+    /// 类型定义所在的位置
+    pub span: Span,
+    /// 类型别名所指向的类型，其他类型为 `None`
+    pub aliased: Option<RSDLType>,
+    /// 类型定义是否是内联的
+    pub is_inline: bool,
+    /// 类型参数的数量，非泛型类型为 0
+    pub arity: usize,
+    /// 类型是否是只包含标量变体、不带公共字段的非泛型和类型，这样的和类型可以作为映射的键
+    pub is_enum: bool
+}
+
+impl KnownType {
+    fn new(span: &Span, arity: usize) -> Self {
+        Self { span: span.clone(), aliased: None, is_inline: false, arity, is_enum: false }
+    }
+}
+
+/// 可以作为映射的键或者集合的元素的类型的种类，参见 [`ResolveContext::map_key_kind`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapKeyKind {
+    /// `str`
+    String,
+    /// `int`
+    Integer,
+    /// 只包含标量变体的和类型，值是和类型的名称
+    Enum(String),
+    /// native 类型，编译器无法得知它的具体含义
+    Native
+}
+
+/// 和类型在 JSON 中的表示方式，由和类型上的 `[repr(...)]` 注解指定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SumRepr {
+    /// 标签作为对象的一个字段，例如 `{ "$kind": "Circle", "r": 1 }`，这是默认的表示方式
+    Internal,
+    /// 标签和内容分别作为对象的两个字段，例如 `{ "$kind": "Circle", "content": { "r": 1 } }`
+    Adjacent,
+    /// 标签作为对象唯一的键，例如 `{ "Circle": { "r": 1 } }`。不带公共字段时，标量变体被表示为字符串
+    External,
+    /// 不带标签，例如 `{ "r": 1 }`
+    Untagged
+}
+
+/// 和类型的标签，参见 [`ResolveContext::sum_taggings`]
+#[derive(Debug, Clone)]
+pub struct SumTagging {
+    /// 和类型在 JSON 中的表示方式
+    pub repr: SumRepr,
+    /// 标签字段的名称，可以使用 `[discriminant = "..."]` 覆盖命令行参数定义的名称
+    pub discriminant: String,
+    /// [`SumRepr::Adjacent`] 表示方式下内容字段的名称，可以使用 `[content = "..."]` 指定，默认为 `content`
+    pub content: String,
+    /// 使用 `[tag_value("...")]` 指定了标签值的标量变体和构造器，键是变体的名称
+    pub tag_values: HashMap<String, String>
+}
+
+impl SumTagging {
+    /// 标量变体或者构造器的标签值，没有指定时就是变体的名称
+    pub fn tag_value<'a>(&'a self, variant: &'a str) -> &'a str {
+        self.tag_values.get(variant).map(String::as_str).unwrap_or(variant)
+    }
+}
 
 /// `rsdl` 代码生成器工作的上下文
 ///
@@ -12,165 +103,737 @@ use crate::parser::hir::{check_inline, RSDLType, TypeDef, TypeDefInner, AttrItem
 /// - [`crate::codegen::CodeGenerator`]
 pub struct ResolveContext {
     /// 全局注解项
-    pub global_attr: Vec<AttrItem>,
+    ///
+    /// 全局注解是所有类型定义的默认注解，参见 [`ResolveContext::effective_attr`]
+    pub global_attr: Vec<Attr>,
     /// 命令行参数定义的 `discriminant` 名称
     pub discriminant: String,
-    /// 已知的类型
+    /// 已知的类型，键是类型名称
+    pub known_types: HashMap<String, KnownType>,
+    /// 和类型的标量变体
     ///
-    /// 键是类型名称，值是一个三元组，分别是：
-    /// - 类型定义所在的文件
-    /// - 类型定义的类型
-    /// - 类型定义是否是内联的
-    pub known_types: HashMap<String, (String, Option<RSDLType>, bool)>
+    /// 键是和类型的名称，值是它的所有标量变体的名称。带有公共字段的和类型不在其中，
+    /// 因为它们的标量变体不能作为字段的默认值
+    pub scalar_variants: HashMap<String, Vec<String>>,
+    /// 和类型的标签，键是和类型的名称
+    pub sum_taggings: HashMap<String, SumTagging>
 }
 
 impl ResolveContext {
-    pub(crate) fn new(global_attr: Vec<AttrItem>, discriminant: impl ToString) -> Self {
+    pub(crate) fn new(global_attr: Vec<Attr>, discriminant: impl ToString) -> Self {
         Self {
             global_attr,
             discriminant: discriminant.to_string(),
-            known_types: HashMap::new()
+            known_types: HashMap::new(),
+            scalar_variants: HashMap::new(),
+            sum_taggings: HashMap::new()
+        }
+    }
+
+    /// 计算一个类型定义实际生效的注解
+    ///
+    /// 全局注解 `[[...]]` 会被追加到类型定义自身的注解之后。类型定义可以使用
+    /// `[no_inherit(rust_derive, ...)]` 排除指定名称的全局注解，或者使用 `[no_inherit]`
+    /// 排除所有的全局注解。全局注解只作用于类型定义本身，不会作用于字段、构造器和标量变体
+    ///
+    /// [`crate::codegen::codegen`] 在调用代码生成器的 `visit_type_alias`、`visit_simple_type`
+    /// 和 `visit_sum_type` 之前会调用此方法，自行实现 `visit_all_typedefs` 的代码生成器则需要自行调用
+    pub fn effective_attr(&self, attr: &[Attr]) -> SmallVec<[Attr; 2]> {
+        let mut ret = attr.iter().cloned().collect::<SmallVec<[Attr; 2]>>();
+
+        if attr.flag("no_inherit") {
+            return ret;
         }
+
+        // 形式不正确的 no_inherit 已经在 min_resolv_attr 中报告过了，此处忽略即可
+        let excluded = attr.ident_args("no_inherit").unwrap_or_default();
+
+        ret.extend(
+            self.global_attr
+                .iter()
+                .filter(|attr| attr.item.name().is_none_or(|name| !excluded.contains(&name)))
+                .cloned()
+        );
+        ret
     }
 
-    pub(crate) fn min_resolv(&mut self, tyde: &TypeDef) -> Result<(), ()> {
+    pub(crate) fn min_resolv(&mut self, tyde: &TypeDef, diags: &mut Diagnostics) {
         match &tyde.inner {
             TypeDefInner::AliasType(name, ty) => {
-                if let Some((exist_in_file, _, _)) = self.known_types.get(name) {
-                    error!(
-                        "{}: 重复的类型定义 {}，此类型先前已经定义于 {}",
-                        tyde.file,
-                        name,
-                        exist_in_file
-                    );
-                    return Err(());
-                }
-
-                let is_inline = check_inline(&tyde.attr);
-                self.known_types.insert(name.clone(), (tyde.file.clone(), Some(ty.clone()), is_inline));
+                let known_type = KnownType {
+                    span: tyde.span.clone(),
+                    aliased: Some(ty.clone()),
+                    is_inline: check_inline(&tyde.attr),
+                    arity: 0,
+                    is_enum: false
+                };
+                self.define(name, None, known_type, diags);
             },
             TypeDefInner::SimpleType(ctor) => {
-                if let Some((exist_in_file, _, _)) = self.known_types.get(&ctor.name) {
-                    error!(
-                        "{}: 重复的类型定义 {}，此类型先前已经定义于 {}",
-                        tyde.file,
-                        ctor.name,
-                        exist_in_file
-                    );
-                    return Err(());
-                }
-
-                self.known_types.insert(ctor.name.clone(), (tyde.file.clone(), None, false));
+                self.define(&ctor.name, None, KnownType::new(&tyde.span, ctor.type_params.len()), diags);
             },
             TypeDefInner::SumType(sum) => {
-                if let Some((exist_in_file, _, _)) = self.known_types.get(&sum.name) {
-                    error!(
-                        "{}: 重复的类型定义 {}，此类型先前已经定义于 {}",
-                        tyde.file,
-                        sum.name,
-                        exist_in_file
+                let known_type = KnownType {
+                    is_enum: sum.ctors.is_empty() && sum.common_fields.is_empty() && sum.type_params.is_empty(),
+                    ..KnownType::new(&tyde.span, sum.type_params.len())
+                };
+                self.define(&sum.name, None, known_type, diags);
+                let tagging = self.sum_tagging(tyde, sum, diags);
+                self.sum_taggings.insert(sum.name.clone(), tagging);
+                if sum.common_fields.is_empty() {
+                    self.scalar_variants.insert(
+                        sum.name.clone(),
+                        sum.scalar_variants.iter().map(|(_, variant, _)| variant.clone()).collect()
                     );
-                    return Err(());
                 }
 
-                self.known_types.insert(sum.name.clone(), (tyde.file.clone(), None, false));
-
-                if !sum.ctors.is_empty() {
+                // 带有公共字段时，代码生成器可能需要为标量变体生成单独的类型
+                if !sum.ctors.is_empty() || !sum.common_fields.is_empty() {
+                    // 构造器对应的类型只带有它（包括公共字段）使用到的类型参数
                     for (_, ctor) in &sum.ctors {
-                        if let Some((exist_in_file, _, _)) = self.known_types.get(&ctor.name) {
-                            error!(
-                                "{}: 重复的类型定义 {} (和类型 {} 的构造器)，此类型先前已经定义于 {}",
-                                tyde.file,
-                                ctor.name,
-                                sum.name,
-                                exist_in_file
-                            );
-                            return Err(());
-                        }
+                        let arity = used_type_params(
+                            &sum.type_params,
+                            ctor.fields.iter().chain(sum.common_fields.iter())
+                        ).len();
+                        self.define(
+                            &ctor.name,
+                            Some(format!("和类型 {} 的构造器", sum.name)),
+                            KnownType::new(&ctor.span, arity),
+                            diags
+                        );
+                    }
 
-                        self.known_types.insert(ctor.name.clone(), (tyde.file.clone(), None, false));
+                    let arity = used_type_params(&sum.type_params, sum.common_fields.iter()).len();
+                    for (_, variant, span) in &sum.scalar_variants {
+                        self.define(
+                            variant,
+                            Some(format!("和类型 {} 的标量变体", sum.name)),
+                            KnownType::new(span, arity),
+                            diags
+                        );
                     }
+                }
+            }
+        }
+    }
 
-                    for (_, variant) in &sum.scalar_variants {
-                        if let Some((exist_in_file, _, _)) = self.known_types.get(variant) {
-                            error!(
-                                "{}: 重复的类型定义 {} (和类型 {} 的标量变体)，此类型先前已经定义于 {}",
-                                tyde.file,
-                                variant,
-                                sum.name,
-                                exist_in_file
-                            );
-                            return Err(());
-                        }
+    /// 根据和类型实际生效的注解计算和类型的标签
+    fn sum_tagging(&self, tyde: &TypeDef, sum: &SumType, diags: &mut Diagnostics) -> SumTagging {
+        let attr = self.effective_attr(&tyde.attr);
+
+        // 形式不正确的注解会在 min_resolv_attr 中报告，此处忽略即可。全局注解位于类型自身的注解之后，
+        // 因此取第一个值就可以让类型自身的注解覆盖全局注解
+        let repr_args = attr.ident_args("repr").unwrap_or_default();
+        for arg in &repr_args {
+            if !matches!(*arg, "internal" | "adjacent" | "external" | "untagged") {
+                diags.push(Diagnostic::new(tyde.span.clone(), format!(
+                    "和类型 {} 的表示方式 {} 无效，只能是 internal、adjacent、external 或者 untagged",
+                    sum.name,
+                    arg
+                )));
+            }
+        }
+        let repr = match repr_args.first().copied() {
+            Some("adjacent") => SumRepr::Adjacent,
+            Some("external") => SumRepr::External,
+            Some("untagged") => SumRepr::Untagged,
+            _ => SumRepr::Internal
+        };
+
+        let first_string = |attr_list: &[Attr], name: &str| {
+            attr_list
+                .string_values(name)
+                .unwrap_or_default()
+                .first()
+                .map(ToString::to_string)
+        };
+
+        let mut tag_values = HashMap::new();
+        let variants = sum.scalar_variants
+            .iter()
+            .map(|(attr, variant, _)| (attr, variant))
+            .chain(sum.ctors.iter().map(|(attr, ctor)| (attr, &ctor.name)));
+        for (variant_attr, variant) in variants {
+            if let Some(tag_value) = first_string(variant_attr, "tag_value") {
+                tag_values.insert(variant.clone(), tag_value);
+            }
+        }
+
+        SumTagging {
+            repr,
+            discriminant: first_string(&attr, "discriminant").unwrap_or_else(|| self.discriminant.clone()),
+            content: first_string(&attr, "content").unwrap_or_else(|| "content".to_string()),
+            tag_values
+        }
+    }
+
+    pub(crate) fn min_resolv_global_attr(&self, known_attrs: &[AttrSpec], diags: &mut Diagnostics) {
+        chk_attrs(&self.global_attr, AttrPosition::Type, known_attrs, diags);
+    }
+
+    pub(crate) fn min_resolv_attr(
+        &self,
+        tyde: &TypeDef,
+        known_attrs: &[AttrSpec],
+        diags: &mut Diagnostics
+    ) {
+        chk_attrs(&tyde.attr, AttrPosition::Type, known_attrs, diags);
 
-                        self.known_types.insert(variant.clone(), (tyde.file.clone(), None, false));
+        match &tyde.inner {
+            TypeDefInner::AliasType(_, _) => {},
+            TypeDefInner::SimpleType(ctor) => {
+                for field in &ctor.fields {
+                    chk_attrs(&field.attr, AttrPosition::Field, known_attrs, diags);
+                }
+            },
+            TypeDefInner::SumType(sum_type) => {
+                for (attr, _, _) in &sum_type.scalar_variants {
+                    chk_attrs(attr, AttrPosition::Variant, known_attrs, diags);
+                }
+
+                for (attr, ctor) in &sum_type.ctors {
+                    chk_attrs(attr, AttrPosition::Ctor, known_attrs, diags);
+                    for field in &ctor.fields {
+                        chk_attrs(&field.attr, AttrPosition::Field, known_attrs, diags);
                     }
                 }
+
+                for field in &sum_type.common_fields {
+                    chk_attrs(&field.attr, AttrPosition::Field, known_attrs, diags);
+                }
             }
         }
-
-        Ok(())
     }
 
-    pub(crate) fn min_resolv_chk(&self, tyde: &TypeDef) -> Result<(), ()> {
+    pub(crate) fn min_resolv_chk(&self, tyde: &TypeDef, diags: &mut Diagnostics) {
         match &tyde.inner {
-            TypeDefInner::AliasType(name, ty) => if let Err(ident) = self.chktype(ty) {
-                error!(
-                    "{}: 类型别名 {} 引用了未知的类型 {}",
-                    tyde.file,
-                    name,
-                    ident
-                );
-                return Err(());
+            TypeDefInner::AliasType(name, ty) => {
+                for problem in self.chktype(ty, &[]) {
+                    diags.push(Diagnostic::new(tyde.span.clone(), format!(
+                        "类型别名 {} {}",
+                        name,
+                        problem
+                    )));
+                }
             },
             TypeDefInner::SimpleType(ctor) => {
-                for (_, _, ty, name) in &ctor.fields {
-                    if let Err(ident) = self.chktype(ty) {
-                        error!(
-                            "{}: 类型 {} 的字段 {} 引用了未知的类型 {}",
-                            tyde.file,
+                self.chk_type_params(&ctor.name, &ctor.type_params, &tyde.span, ctor.fields.iter(), diags);
+                let rule = rename_rule(&self.effective_attr(&tyde.attr));
+                self.chk_wire_names(&ctor.name, ctor.fields.iter().map(|field| (rule, field)), diags);
+
+                for field in &ctor.fields {
+                    for problem in self.chktype(&field.ty, &ctor.type_params) {
+                        diags.push(Diagnostic::new(field.span.clone(), format!(
+                            "类型 {} 的字段 {} {}",
                             ctor.name,
-                            name,
-                            ident
-                        );
-                        return Err(());
+                            field.name,
+                            problem
+                        )));
                     }
+                    self.chk_default(field, &ctor.type_params, diags);
                 }
             },
             TypeDefInner::SumType(sum_type) => {
+                let all_fields = sum_type
+                    .ctors
+                    .iter()
+                    .flat_map(|(_, ctor)| ctor.fields.iter())
+                    .chain(sum_type.common_fields.iter());
+                self.chk_type_params(&sum_type.name, &sum_type.type_params, &tyde.span, all_fields, diags);
+
+                let attr = self.effective_attr(&tyde.attr);
+                let sum_rule = rename_rule(&attr);
+
+                if let Some(tagging) = self.sum_taggings.get(&sum_type.name) {
+                    self.chk_sum_tagging(sum_type, sum_rule, tagging, &tyde.span, diags);
+                }
+                for (ctor_attr, ctor) in &sum_type.ctors {
+                    let ctor_rule = rename_rule(ctor_attr).or(sum_rule);
+                    let fields = ctor.fields
+                        .iter()
+                        .map(|field| (ctor_rule, field))
+                        .chain(sum_type.common_fields.iter().map(|field| (sum_rule, field)));
+                    self.chk_wire_names(&ctor.name, fields, diags);
+                }
+
                 for (_, ctor) in &sum_type.ctors {
-                    for (_, _, ty, name) in &ctor.fields {
-                        if let Err(ident) = self.chktype(ty) {
-                            error!(
-                                "{}: 类型 {} 构造器 {} 的字段 {} 引用了未知的类型 {}",
-                                tyde.file,
+                    if !ctor.type_params.is_empty() {
+                        diags.push(Diagnostic::new(ctor.span.clone(), format!(
+                            "和类型 {} 的构造器 {} 不能声明类型参数，请在和类型上声明",
+                            sum_type.name,
+                            ctor.name
+                        )));
+                    }
+
+                    for field in &ctor.fields {
+                        for problem in self.chktype(&field.ty, &sum_type.type_params) {
+                            diags.push(Diagnostic::new(field.span.clone(), format!(
+                                "类型 {} 构造器 {} 的字段 {} {}",
                                 sum_type.name,
                                 ctor.name,
-                                name,
-                                ident
-                            );
-                            return Err(());
+                                field.name,
+                                problem
+                            )));
                         }
+                        self.chk_default(field, &sum_type.type_params, diags);
+
+                        if let Some(common_field) = sum_type
+                            .common_fields
+                            .iter()
+                            .find(|common_field| common_field.name == field.name)
+                        {
+                            diags.push(Diagnostic::new(field.span.clone(), format!(
+                                "类型 {} 构造器 {} 的字段 {} 与公共字段重名，公共字段定义于 {}",
+                                sum_type.name,
+                                ctor.name,
+                                field.name,
+                                common_field.span
+                            )));
+                        }
+                    }
+                }
+
+                for field in &sum_type.common_fields {
+                    for problem in self.chktype(&field.ty, &sum_type.type_params) {
+                        diags.push(Diagnostic::new(field.span.clone(), format!(
+                            "类型 {} 的公共字段 {} {}",
+                            sum_type.name,
+                            field.name,
+                            problem
+                        )));
+                    }
+                    self.chk_default(field, &sum_type.type_params, diags);
+                }
+            }
+        }
+    }
+
+    /// 检查同一个类型（或者构造器）的字段在 JSON 中的名称是否重复
+    fn chk_wire_names<'a>(
+        &self,
+        type_name: &str,
+        fields: impl Iterator<Item = (Option<RenameRule>, &'a Field)>,
+        diags: &mut Diagnostics
+    ) {
+        let mut seen: HashMap<String, &Field> = HashMap::new();
+        for (rule, field) in fields {
+            let name = wire_name(rule, field);
+            if let Some(exist) = seen.get(&name) {
+                diags.push(Diagnostic::new(field.span.clone(), format!(
+                    "类型 {} 的字段 {} 与字段 {} 在 JSON 中的名称都是 {}",
+                    type_name,
+                    field.name,
+                    exist.name,
+                    name
+                )));
+            } else {
+                seen.insert(name, field);
+            }
+        }
+    }
+
+    /// 检查和类型的标签：标签值不能重复，标签字段不能与字段重名，并且表示方式要能够区分所有的变体
+    fn chk_sum_tagging(
+        &self,
+        sum_type: &SumType,
+        sum_rule: Option<RenameRule>,
+        tagging: &SumTagging,
+        span: &Span,
+        diags: &mut Diagnostics
+    ) {
+        let variants = sum_type.scalar_variants
+            .iter()
+            .map(|(_, variant, span)| (variant, span))
+            .chain(sum_type.ctors.iter().map(|(_, ctor)| (&ctor.name, &ctor.span)));
+
+        let mut seen: HashMap<&str, &str> = HashMap::new();
+        for (variant, variant_span) in variants {
+            let tag_value = tagging.tag_value(variant);
+            if let Some(exist) = seen.insert(tag_value, variant) {
+                diags.push(Diagnostic::new(variant_span.clone(), format!(
+                    "和类型 {} 的变体 {} 与 {} 的标签值都是 {}",
+                    sum_type.name,
+                    variant,
+                    exist,
+                    tag_value
+                )));
+            }
+        }
+
+        match tagging.repr {
+            SumRepr::Internal => {
+                // 标签字段与其他字段一起出现在对象中，因此比较的是字段的线上名称
+                let fields = sum_type.ctors
+                    .iter()
+                    .flat_map(|(ctor_attr, ctor)| {
+                        let ctor_rule = rename_rule(ctor_attr).or(sum_rule);
+                        ctor.fields.iter().map(move |field| (ctor_rule, field))
+                    })
+                    .chain(sum_type.common_fields.iter().map(|field| (sum_rule, field)));
+                for (rule, field) in fields {
+                    let name = wire_name(rule, field);
+                    if name == tagging.discriminant {
+                        diags.push(Diagnostic::new(field.span.clone(), format!(
+                            "和类型 {} 的字段 {} 在 JSON 中的名称 {} 与标签字段重名，请使用 [discriminant = \"...\"] 指定其他的标签字段名称",
+                            sum_type.name,
+                            field.name,
+                            name
+                        )));
                     }
                 }
+            },
+            SumRepr::Adjacent if tagging.discriminant == tagging.content => {
+                diags.push(Diagnostic::new(span.clone(), format!(
+                    "和类型 {} 的标签字段和内容字段不能都叫做 {}",
+                    sum_type.name,
+                    tagging.content
+                )));
+            },
+            // 没有公共字段时标量变体没有可以序列化的内容；有公共字段时标量变体只包含公共字段，
+            // 任何构造器的对象都能被反序列化为它，构造器自己的字段会被悄悄丢弃
+            SumRepr::Untagged => {
+                for (_, variant, variant_span) in &sum_type.scalar_variants {
+                    diags.push(Diagnostic::new(variant_span.clone(), format!(
+                        "使用 untagged 表示的和类型 {} 不能包含标量变体 {}，因为标量变体没有自己的字段，无法与其他变体区分",
+                        sum_type.name,
+                        variant
+                    )));
+                }
+            },
+            _ => {}
+        }
+    }
+
+    /// 检查类型参数：类型参数不能重复、不能与已知的类型重名，并且必须被至少一个字段使用
+    fn chk_type_params<'a>(
+        &self,
+        type_name: &str,
+        type_params: &[String],
+        span: &Span,
+        fields: impl Iterator<Item = &'a Field> + Clone,
+        diags: &mut Diagnostics
+    ) {
+        let used = used_type_params(type_params, fields);
+
+        for (idx, param) in type_params.iter().enumerate() {
+            if type_params[..idx].contains(param) {
+                diags.push(Diagnostic::new(span.clone(), format!(
+                    "类型 {} 的类型参数 {} 重复出现",
+                    type_name,
+                    param
+                )));
+            } else if let Some(known_type) = self.known_types.get(param) {
+                diags.push(Diagnostic::new(span.clone(), format!(
+                    "类型 {} 的类型参数 {} 与定义于 {} 的类型重名",
+                    type_name,
+                    param,
+                    known_type.span
+                )));
+            } else if !used.contains(&param.as_str()) {
+                diags.push(Diagnostic::new(span.clone(), format!(
+                    "类型 {} 的类型参数 {} 没有被任何字段使用",
+                    type_name,
+                    param
+                )));
             }
         }
+    }
+
+    /// 检查字段的默认值是否与字段的类型匹配
+    fn chk_default(&self, field: &Field, type_params: &[String], diags: &mut Diagnostics) {
+        let Some(default) = &field.default else { return; };
 
-        Ok(())
+        let result = self.chk_literal(&field.ty, type_params, &default.value);
+
+        if let Err(reason) = result {
+            diags.push(Diagnostic::new(default.span.clone(), format!(
+                "字段 {} 的默认值与字段的类型不匹配: {}",
+                field.name,
+                reason
+            )));
+        }
     }
 
-    fn chktype<'a>(&self, ty: &'a RSDLType) -> Result<(), &'a str> {
+    /// 检查字面量是否与类型匹配。`type_params` 是当前作用域中的类型参数，
+    /// 类型中任何位置出现的类型参数都不能指定默认值
+    fn chk_literal(&self, ty: &RSDLType, type_params: &[String], literal: &Literal) -> Result<(), String> {
         match ty {
+            RSDLType::Identifier(ident) if type_params.contains(ident) => {
+                Err(format!("类型参数 {} 不能指定默认值", ident))
+            },
             RSDLType::Identifier(ident) => {
-                if self.known_types.get(ident.as_str()).is_none() {
-                    return Err(ident.as_str());
+                if let Some(variants) = self.scalar_variants.get(ident) {
+                    return match literal {
+                        Literal::Variant(variant) if variants.contains(variant) => Ok(()),
+                        Literal::Variant(variant) => Err(format!("和类型 {} 没有标量变体 {}", ident, variant)),
+                        _ => Err(format!("应该是和类型 {} 的标量变体", ident))
+                    };
+                }
+
+                match (ident.as_str(), literal) {
+                    ("int", Literal::Integer(_)) => Ok(()),
+                    ("int", _) => Err("应该是整数".to_string()),
+                    ("float", Literal::Integer(_) | Literal::Float(_)) => Ok(()),
+                    ("float", _) => Err("应该是数字".to_string()),
+                    ("bool", Literal::Bool(_)) => Ok(()),
+                    ("bool", _) => Err("应该是布尔值".to_string()),
+                    ("str", Literal::String(_)) => Ok(()),
+                    ("str", _) => Err("应该是字符串".to_string()),
+                    _ => match self.known_types.get(ident).map(|known_type| &known_type.aliased) {
+                        // 类型别名不能带有类型参数
+                        Some(Some(aliased)) => self.chk_literal(aliased, &[], literal),
+                        // 未知的类型已经报告过了
+                        None => Ok(()),
+                        Some(_) => Err(format!(
+                            "类型 {} 不是基本类型、列表或者不带公共字段的和类型，不能指定默认值",
+                            ident
+                        ))
+                    }
                 }
             },
-            RSDLType::List(inner) => return self.chktype(inner),
-            RSDLType::Record(inner) => return self.chktype(inner),
-            _ => {}
+            // 无法得知 native 类型的具体含义，只要求默认值不是标量变体或者列表
+            RSDLType::Native(_) => match literal {
+                Literal::Variant(_) | Literal::List(_) => Err("native 类型的默认值只能是数字、布尔值或者字符串".to_string()),
+                _ => Ok(())
+            },
+            RSDLType::List(inner) => match literal {
+                Literal::List(items) => items.iter().try_for_each(|item| self.chk_literal(inner, type_params, item)),
+                _ => Err("应该是列表".to_string())
+            },
+            RSDLType::Array(inner, len) => match literal {
+                Literal::List(items) if items.len() == *len => {
+                    items.iter().try_for_each(|item| self.chk_literal(inner, type_params, item))
+                },
+                _ => Err(format!("应该是包含 {} 个元素的列表", len))
+            },
+            RSDLType::Tuple(types) => match literal {
+                Literal::List(items) if items.len() == types.len() => {
+                    types.iter().zip(items).try_for_each(|(ty, item)| self.chk_literal(ty, type_params, item))
+                },
+                _ => Err(format!("应该是包含 {} 个元素的列表", types.len()))
+            },
+            RSDLType::Map(..) => Err("map 类型不能指定默认值".to_string()),
+            RSDLType::Set(_) => Err("set 类型不能指定默认值".to_string()),
+            RSDLType::Generic(ident, _) => Err(format!("泛型类型 {} 不能指定默认值", ident))
+        }
+    }
+
+    /// 展开类型别名，返回别名最终指向的类型。不是别名的类型原样返回
+    pub fn resolve_alias<'a>(&'a self, ty: &'a RSDLType) -> &'a RSDLType {
+        let mut ty = ty;
+        // 限制展开别名的次数，避免循环定义的别名导致死循环
+        for _ in 0..=self.known_types.len() {
+            let RSDLType::Identifier(ident) = ty else { break; };
+            match self.known_types.get(ident).and_then(|known_type| known_type.aliased.as_ref()) {
+                Some(aliased) => ty = aliased,
+                None => break
+            }
+        }
+        ty
+    }
+
+    /// 展开类型别名，如果类型是 `int`、`float`、`bool` 或者 `str`（或者它们的别名），返回基本类型的名称
+    pub fn builtin_type<'a>(&'a self, ty: &'a RSDLType) -> Option<&'a str> {
+        let mut ty = ty;
+        // 限制展开别名的次数，避免循环定义的别名导致死循环
+        for _ in 0..=self.known_types.len() {
+            let RSDLType::Identifier(ident) = ty else { return None; };
+            if matches!(ident.as_str(), "int" | "float" | "bool" | "str") {
+                return Some(ident);
+            }
+            ty = self.known_types.get(ident)?.aliased.as_ref()?;
+        }
+        None
+    }
+
+    /// 判断一个类型能否作为映射的键或者集合的元素，不能时返回 `None`
+    ///
+    /// 能作为键的类型有 `str`、`int`、只包含标量变体的和类型、native 类型以及它们的别名。
+    /// `float` 和 `bool` 虽然也是 native 类型，但是不能作为键
+    pub fn map_key_kind(&self, ty: &RSDLType) -> Option<MapKeyKind> {
+        let mut ty = ty;
+        // 限制展开别名的次数，避免循环定义的别名导致死循环
+        for _ in 0..=self.known_types.len() {
+            match ty {
+                RSDLType::Identifier(ident) => match ident.as_str() {
+                    "str" => return Some(MapKeyKind::String),
+                    "int" => return Some(MapKeyKind::Integer),
+                    "float" | "bool" => return None,
+                    _ => {
+                        let known_type = self.known_types.get(ident)?;
+                        if known_type.is_enum {
+                            return Some(MapKeyKind::Enum(ident.clone()));
+                        }
+                        ty = known_type.aliased.as_ref()?;
+                    }
+                },
+                RSDLType::Native(_) => return Some(MapKeyKind::Native),
+                _ => return None
+            }
+        }
+        None
+    }
+
+    /// 将一个类型加入已知类型表。如果该类型已经定义过，则报告错误并保留先前的定义
+    fn define(
+        &mut self,
+        name: &str,
+        note: Option<String>,
+        known_type: KnownType,
+        diags: &mut Diagnostics
+    ) {
+        if let Some(exist) = self.known_types.get(name) {
+            let name = match note {
+                Some(note) => format!("{} ({})", name, note),
+                None => name.to_string()
+            };
+
+            diags.push(Diagnostic::new(known_type.span, format!(
+                "重复的类型定义 {}，此类型先前已经定义于 {}",
+                name,
+                exist.span
+            )));
+            return;
+        }
+
+        self.known_types.insert(name.to_string(), known_type);
+    }
+
+    /// 检查类型中引用的所有标识符，返回发现的所有问题的描述，例如“引用了未知的类型 X”
+    ///
+    /// `type_params` 是当前作用域中的类型参数
+    fn chktype(&self, ty: &RSDLType, type_params: &[String]) -> Vec<String> {
+        let mut problems = Vec::new();
+        self.imp_chktype(ty, type_params, &mut problems);
+        problems
+    }
+
+    fn imp_chktype(&self, ty: &RSDLType, type_params: &[String], problems: &mut Vec<String>) {
+        match ty {
+            RSDLType::Identifier(ident) if type_params.contains(ident) => {},
+            RSDLType::Identifier(ident) => match self.known_types.get(ident) {
+                None => problems.push(format!("引用了未知的类型 {}", ident)),
+                Some(known_type) if known_type.arity != 0 => problems.push(format!(
+                    "引用的泛型类型 {} 缺少类型参数，它需要 {} 个类型参数",
+                    ident,
+                    known_type.arity
+                )),
+                Some(_) => {}
+            },
+            RSDLType::List(inner) => self.imp_chktype(inner, type_params, problems),
+            RSDLType::Array(inner, len) => {
+                if *len == 0 {
+                    problems.push(format!("引用的数组类型的长度必须是不超过 {} 的正整数", usize::MAX));
+                }
+                self.imp_chktype(inner, type_params, problems);
+            },
+            RSDLType::Tuple(items) => {
+                for item in items {
+                    self.imp_chktype(item, type_params, problems);
+                }
+            },
+            RSDLType::Map(key, value) => {
+                let key_problems = problems.len();
+                self.imp_chktype(key, type_params, problems);
+                if problems.len() == key_problems {
+                    match self.map_key_kind(key) {
+                        None => problems.push(format!("引用的 map 类型的键类型{}", KEY_TYPE_REQUIREMENT)),
+                        // JSON 对象的键只能是字符串，只有 external 表示方式会将标量变体序列化为字符串
+                        Some(MapKeyKind::Enum(name)) if self.sum_taggings
+                            .get(&name)
+                            .is_some_and(|tagging| tagging.repr != SumRepr::External) => problems.push(format!(
+                                "引用的 map 类型的键类型 {} 是和类型，它必须使用 [repr(external)]，其他表示方式下标量变体不会被序列化为字符串",
+                                name
+                            )),
+                        Some(_) => {}
+                    }
+                }
+                self.imp_chktype(value, type_params, problems);
+            },
+            RSDLType::Set(inner) => {
+                let inner_problems = problems.len();
+                self.imp_chktype(inner, type_params, problems);
+                if problems.len() == inner_problems && self.map_key_kind(inner).is_none() {
+                    problems.push(format!("引用的 set 类型的元素类型{}", KEY_TYPE_REQUIREMENT));
+                }
+            },
+            RSDLType::Generic(ident, args) => {
+                if type_params.contains(ident) {
+                    problems.push(format!("引用的类型参数 {} 不能带有类型参数", ident));
+                } else {
+                    match self.known_types.get(ident) {
+                        None => problems.push(format!("引用了未知的类型 {}", ident)),
+                        Some(known_type) if known_type.arity != args.len() => problems.push(format!(
+                            "引用的类型 {} 需要 {} 个类型参数，但是提供了 {} 个",
+                            ident,
+                            known_type.arity,
+                            args.len()
+                        )),
+                        Some(_) => {}
+                    }
+                }
+
+                for arg in args {
+                    self.imp_chktype(arg, type_params, problems);
+                }
+            },
+            RSDLType::Native(_) => {}
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::testing::{compile_err, compile_ok};
+
+    #[test]
+    fn defaults_must_match_field_types() {
+        compile_ok("Mode : Fast | Slow\nA(x: float = 1, m: Mode = Fast, p: (int, str) = [1, \"a\"])", "rust");
+
+        let errors = compile_err("Mode : Fast | Slow\nA(x: int = 1.5, m: Mode = Medium, xs: [int; 2] = [1])", "rust");
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+    }
+
+    #[test]
+    fn map_keys_must_serialize_as_strings() {
+        compile_ok("[repr(external)]\nMode : Fast | Slow\nA(a: map<str, int>, b: map<int, int>, c: map<Mode, int>)", "rust");
+        compile_ok("Mode : Fast | Slow\nA(s: set<Mode>)", "rust");
+
+        let errors = compile_err("Mode : Fast | Slow\nA(m: map<Mode, int>)", "rust");
+        assert!(errors[0].contains("[repr(external)]"), "{:?}", errors);
+
+        let errors = compile_err("Shape : Circle(r: float)\nA(a: map<float, int>, b: map<Shape, int>, c: set<bool>)", "rust");
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+    }
+
+    #[test]
+    fn type_params_cannot_have_defaults_at_any_depth() {
+        for field in ["x: T = 1", "xs: list<T> = [1]", "p: (T, int) = [1, 2]"] {
+            let errors = compile_err(&format!("Box<T>({})", field), "rust");
+            assert!(errors[0].contains("类型参数 T 不能指定默认值"), "{:?}", errors);
+        }
+    }
+
+    #[test]
+    fn tagging_conflicts_are_errors() {
+        let errors = compile_err("A : A1(v: int) | [tag_value(\"A1\")] A2", "rust");
+        assert!(errors[0].contains("标签值都是 A1"), "{:?}", errors);
+
+        let errors = compile_err("[discriminant = \"kind\"]\nB : B1(kind: int)", "rust");
+        assert!(errors[0].contains("与标签字段重名"), "{:?}", errors);
+        compile_ok("[repr(adjacent)]\n[discriminant = \"kind\"]\nB : B1(kind: int)", "rust");
+
+        let errors = compile_err("B : B1([rename(\"$kind\")] kind: int)", "rust");
+        assert!(errors[0].contains("在 JSON 中的名称 $kind 与标签字段重名"), "{:?}", errors);
+        let errors = compile_err("B : B1(x: int)\n  attributes ([rename(typescript = \"$kind\")] k: int)", "rust");
+        assert!(errors[0].starts_with("test.rsdl:2:46:"), "{:?}", errors);
+        compile_ok("[discriminant = \"isKind\"]\nB : B1(kind: int) | B2(is_kind: int)", "rust");
+        compile_err("[discriminant = \"isKind\"]\nB : B1(kind: int) | [rename_all(camelCase)] B2(is_kind: int)", "rust");
+
+        let errors = compile_err("[repr(untagged)]\nC : C1(v: int) | C2", "rust");
+        assert!(errors[0].starts_with("test.rsdl:2:18:"), "{:?}", errors);
 
-        Ok(())
+        let errors = compile_err("[repr(untagged)]\nE : BinOp(op: str) | Wildcard attributes (line: int)", "rust");
+        assert!(errors[0].starts_with("test.rsdl:2:22:"), "{:?}", errors);
     }
 }
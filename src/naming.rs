@@ -0,0 +1,298 @@
+//! 字段的命名规则
+//!
+//! 同一个字段在 JSON 中的名称（下称线上名称）以及在不同语言中的标识符可以互不相同：
+//!
+//! - `[rename_all(camelCase)]` 可以用于类型定义（包括全局注解）和构造器，指定其中所有字段的线上名称的命名风格
+//! - `[rename("x")]` 或者 `[rename = "x"]` 用于字段，直接指定字段的线上名称
+//! - `[rename(rust = "a", typescript = "b")]` 用于字段，指定字段在某个语言中的标识符，键是代码生成器的语言标识符。
+//!   TypeScript 直接使用线上名称作为属性名称，因此 `typescript = "b"` 同时指定了字段的线上名称，
+//!   其他语言（例如 Rust 的 `#[serde(rename)]`）也会使用这个线上名称
+//!
+//! 代码生成器负责让所有语言的线上名称保持一致，参见 [`crate::codegen::CodeGenerator::field_name`]
+
+use crate::diagnostic::{Diagnostic, Diagnostics};
+use crate::parser::hir::{Attr, AttrItem, AttrQuery, Field};
+
+/// 命名风格，与 serde 的 `rename_all` 支持的风格相同
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenameRule {
+    /// `lowercase`
+    Lower,
+    /// `UPPERCASE`
+    Upper,
+    /// `PascalCase`
+    Pascal,
+    /// `camelCase`
+    Camel,
+    /// `snake_case`
+    Snake,
+    /// `SCREAMING_SNAKE_CASE`
+    ScreamingSnake,
+    /// `kebab-case`
+    Kebab,
+    /// `SCREAMING-KEBAB-CASE`
+    ScreamingKebab
+}
+
+/// 所有命名风格的名称，用于错误信息
+const RULE_NAMES: &[(&str, RenameRule)] = &[
+    ("lowercase", RenameRule::Lower),
+    ("UPPERCASE", RenameRule::Upper),
+    ("PascalCase", RenameRule::Pascal),
+    ("camelCase", RenameRule::Camel),
+    ("snake_case", RenameRule::Snake),
+    ("SCREAMING_SNAKE_CASE", RenameRule::ScreamingSnake),
+    ("kebab-case", RenameRule::Kebab),
+    ("SCREAMING-KEBAB-CASE", RenameRule::ScreamingKebab)
+];
+
+impl RenameRule {
+    /// 根据名称查找命名风格，例如 `camelCase`
+    pub fn from_name(name: &str) -> Option<Self> {
+        RULE_NAMES
+            .iter()
+            .find(|(rule_name, _)| *rule_name == name)
+            .map(|(_, rule)| *rule)
+    }
+
+    /// 将一个名称转换为这种命名风格。名称会先按照下划线、连字符以及大小写的变化拆分成单词
+    ///
+    /// # 示例
+    ///
+    /// ```rust
+    /// use rsdl::naming::RenameRule;
+    ///
+    /// assert_eq!(RenameRule::Camel.apply("line_no"), "lineNo");
+    /// assert_eq!(RenameRule::Snake.apply("HTTPServer"), "http_server");
+    /// assert_eq!(RenameRule::Kebab.apply("lineNo"), "line-no");
+    /// ```
+    pub fn apply(self, name: &str) -> String {
+        let words = split_words(name);
+
+        let capitalize = |word: &String| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new()
+            }
+        };
+
+        match self {
+            RenameRule::Lower => words.concat(),
+            RenameRule::Upper => words.concat().to_uppercase(),
+            RenameRule::Pascal => words.iter().map(capitalize).collect(),
+            RenameRule::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(idx, word)| if idx == 0 { word.clone() } else { capitalize(word) })
+                .collect(),
+            RenameRule::Snake => words.join("_"),
+            RenameRule::ScreamingSnake => words.join("_").to_uppercase(),
+            RenameRule::Kebab => words.join("-"),
+            RenameRule::ScreamingKebab => words.join("-").to_uppercase()
+        }
+    }
+}
+
+/// 将名称拆分为小写的单词，例如 `HTTPServer_port` 会被拆分为 `http`、`server` 和 `port`
+fn split_words(name: &str) -> Vec<String> {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut words = Vec::new();
+    let mut word = String::new();
+
+    for (idx, &c) in chars.iter().enumerate() {
+        if c == '_' || c == '-' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+
+        if c.is_uppercase() && !word.is_empty() {
+            let prev = chars[idx - 1];
+            let next_is_lower = chars.get(idx + 1).is_some_and(|next| next.is_lowercase());
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_is_lower) {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+
+        word.extend(c.to_lowercase());
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// 注解列表中第一个 `rename_all` 注解指定的命名风格
+///
+/// 全局注解和外层类型的注解应该放在列表的后面，这样内层的注解可以覆盖它们。
+/// 形式不正确的 `rename_all` 会被忽略，它们在检查注解时被报告
+pub fn rename_rule(attr_list: &[Attr]) -> Option<RenameRule> {
+    attr_list
+        .call_args("rename_all")
+        .unwrap_or_default()
+        .into_iter()
+        .find_map(|arg| match arg {
+            AttrItem::Identifier(name) | AttrItem::String(name) => RenameRule::from_name(name),
+            _ => None
+        })
+}
+
+/// 字段的线上名称，即字段在 JSON 中的名称
+///
+/// 字段上的 `[rename("x")]` 优先，其次是 `[rename(typescript = "x")]`（TypeScript 的属性名称就是线上名称），
+/// 再次是 `rule` 指定的命名风格，最后是字段在 RSDL 中的名称
+pub fn wire_name(rule: Option<RenameRule>, field: &Field) -> String {
+    if let Some(name) = plain_rename(&field.attr).or_else(|| target_rename(&field.attr, TYPESCRIPT)) {
+        return name.to_string();
+    }
+
+    match rule {
+        Some(rule) => rule.apply(&field.name),
+        None => field.name.clone()
+    }
+}
+
+/// 字段上的 `[rename(lang = "...")]` 为语言 `lang` 指定的标识符
+pub fn target_name<'a>(field: &'a Field, lang: &str) -> Option<&'a str> {
+    target_rename(&field.attr, lang)
+}
+
+/// TypeScript 代码生成器的语言标识符，它的属性名称总是线上名称
+const TYPESCRIPT: &str = "typescript";
+
+/// 注解列表中 `[rename("x")]` 或者 `[rename = "x"]` 指定的线上名称
+fn plain_rename(attr_list: &[Attr]) -> Option<&str> {
+    attr_list.all("rename").into_iter().find_map(|attr| match &attr.item {
+        AttrItem::Assignment(_, value) => match value.as_ref() {
+            AttrItem::String(name) => Some(name.as_str()),
+            _ => None
+        },
+        AttrItem::CallAlike(_, args) => match args.as_slice() {
+            [AttrItem::String(name)] => Some(name.as_str()),
+            _ => None
+        },
+        _ => None
+    })
+}
+
+/// 注解列表中 `[rename(lang = "...")]` 为语言 `lang` 指定的名称
+fn target_rename<'a>(attr_list: &'a [Attr], lang: &str) -> Option<&'a str> {
+    attr_list
+        .call_args("rename")
+        .unwrap_or_default()
+        .into_iter()
+        .find_map(|arg| match arg {
+            AttrItem::Assignment(target, value) if target == lang => match value.as_ref() {
+                AttrItem::String(name) => Some(name.as_str()),
+                _ => None
+            },
+            _ => None
+        })
+}
+
+/// 检查一个注解列表中的 `rename_all` 和 `rename` 注解的参数
+pub(crate) fn check_rename_attrs(attr_list: &[Attr], diags: &mut Diagnostics) {
+    for attr in attr_list {
+        match (attr.item.name(), &attr.item) {
+            (Some("rename_all"), AttrItem::CallAlike(_, args)) => {
+                let valid = match args.as_slice() {
+                    [AttrItem::Identifier(name) | AttrItem::String(name)] => RenameRule::from_name(name).is_some(),
+                    _ => false
+                };
+
+                if !valid {
+                    let rule_names = RULE_NAMES
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join("、");
+                    diags.push(Diagnostic::new(attr.span.clone(), format!(
+                        "rename_all 的参数应该是以下命名风格之一: {}",
+                        rule_names
+                    )));
+                }
+            },
+            (Some("rename"), AttrItem::CallAlike(_, args)) => {
+                let valid = match args.as_slice() {
+                    [AttrItem::String(_)] => true,
+                    _ => !args.is_empty() && args.iter().all(|arg| matches!(
+                        arg,
+                        AttrItem::Assignment(_, value) if matches!(value.as_ref(), AttrItem::String(_))
+                    ))
+                };
+
+                if !valid {
+                    diags.push(Diagnostic::new(
+                        attr.span.clone(),
+                        "rename 应该写作 [rename(\"name\")] 或者 [rename(rust = \"a\", typescript = \"b\")]"
+                    ));
+                }
+            },
+            _ => {}
+        }
+    }
+
+    // TypeScript 的属性名称就是线上名称，它不能与 [rename("...")] 指定的线上名称矛盾
+    if let (Some(name), Some(ts_name)) = (plain_rename(attr_list), target_rename(attr_list, TYPESCRIPT)) {
+        if name != ts_name {
+            let attr = attr_list.all("rename")[0];
+            diags.push(Diagnostic::new(attr.span.clone(), format!(
+                "TypeScript 直接使用线上名称，字段在 TypeScript 中的名称 {} 与线上名称 {} 矛盾",
+                ts_name,
+                name
+            )));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RenameRule;
+    use crate::compiler::testing::{compile_err, compile_ok};
+
+    #[test]
+    fn rules_split_words() {
+        assert_eq!(RenameRule::Pascal.apply("line_no"), "LineNo");
+        assert_eq!(RenameRule::ScreamingSnake.apply("lineNo"), "LINE_NO");
+        assert_eq!(RenameRule::ScreamingKebab.apply("x1_y"), "X1-Y");
+        assert_eq!(RenameRule::Lower.apply("HTTPServer"), "httpserver");
+        assert_eq!(RenameRule::from_name("kebab-case"), Some(RenameRule::Kebab));
+        assert_eq!(RenameRule::from_name("Kebab"), None);
+    }
+
+    #[test]
+    fn targets_agree_on_wire_names() {
+        let src = "[rust_serde]\n[rename_all(camelCase)]\nA(line_no: int, [rename(\"x-y\")] a: int, [rename(rust = \"kind_\")] kind: int, [rename(rust = \"r#ref\", typescript = \"$ref\")] reference: str)";
+
+        let rust = compile_ok(src, "rust");
+        assert!(rust.contains("#[serde(rename = \"lineNo\")]\n    pub line_no: i64,"), "{}", rust);
+        assert!(rust.contains("#[serde(rename = \"x-y\")]\n    pub a: i64,"), "{}", rust);
+        assert!(rust.contains("#[serde(rename = \"kind\")]\n    pub kind_: i64,"), "{}", rust);
+        assert!(rust.contains("#[serde(rename = \"$ref\")]\n    pub r#ref: String,"), "{}", rust);
+
+        let ts = compile_ok(src, "typescript");
+        assert!(ts.contains("lineNo: number,\n    \"x-y\": number,\n    kind: number,\n    $ref: string,"), "{}", ts);
+    }
+
+    #[test]
+    fn constructor_rule_overrides_sum_type_rule() {
+        let src = "[rename_all(\"kebab-case\")]\nS : [rename_all(camelCase)] A(line_no: int) | B(line_no: int)\n  attributes (col_no: int)";
+        let ts = compile_ok(src, "typescript");
+        assert!(ts.contains("lineNo: number,"), "{}", ts);
+        assert!(ts.contains("\"line-no\": number,"), "{}", ts);
+        assert!(ts.contains("\"col-no\": number,"), "{}", ts);
+    }
+
+    #[test]
+    fn invalid_renames_are_errors() {
+        let errors = compile_err(
+            "[rename_all(fooCase)]\nA([rename(1)] x: int, [rename(\"j\")] [rename(typescript = \"k\")] y: int)\n[rename_all(camelCase)]\nB(a_b: int, aB: int)",
+            "rust"
+        );
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+        assert!(errors[3].contains("在 JSON 中的名称都是 aB"), "{:?}", errors);
+    }
+}
@@ -0,0 +1,362 @@
+//! Zephyr ASDL 兼容模式
+//!
+//! 这个模块可以读取未经修改的 Zephyr ASDL 文件（例如 CPython 的 `Python.asdl`），
+//! 并将其转换为 RSDL HIR，之后的处理与 RSDL 源代码完全相同：
+//!
+//! - `module Name { ... }` 中的定义会被直接展开，模块名和版本号会被忽略
+//! - `T* name` 会被转换为 `name: [T]`，`T? name` 会被转换为 `name?: T`
+//! - 省略了名称的字段使用类型名作为字段名
+//! - 和类型的 `attributes (...)` 会被转换为和类型的公共字段（参见 [`SumType::common_fields`]），
+//!   积类型的 `attributes (...)` 中的字段则会被追加到它的字段之后
+//! - 和类型中不含字段的构造器会被转换为标量变体
+//! - ASDL 的内置类型 `identifier` 和 `string` 会被映射为 `str`，`int` 保持不变。
+//!   其他内置类型（`constant`、`bytes`、`object` 等）需要由用户自行定义
+//! - ASDL 中的类型通常是互递归的，因此没有 `*` 修饰并且其类型可能（不经过 `*` 修饰的字段）
+//!   递归地包含字段所在类型的字段会被添加 `[boxed]` 注解，例如 `expr` 中类型为 `expr` 的字段。
+//!   `expr_context` 这样不会递归的类型不会被装箱
+//!
+//! # 示例
+//!
+//! ```asdl
+//! module Python
+//! {
+//!     expr = BinOp(expr left, operator op, expr right)
+//!          | Name(identifier id, expr_context ctx)
+//!          attributes (int lineno, int col_offset)
+//!
+//!     expr_context = Load | Store | Del
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use pest::iterators::{Pair, Pairs};
+use pest_derive::Parser;
+use smallvec::SmallVec;
+
+use crate::diagnostic::Span;
+use crate::parser::hir::{
+    Attr,
+    AttrItem,
+    Field,
+    Optionality,
+    RSDLType,
+    SumType,
+    TypeConstructor,
+    TypeDef,
+    TypeDefInner
+};
+
+/// 使用 pest 实现的 Zephyr ASDL 解析器，将 ASDL 代码解析为 Pest AST
+///
+/// 一般而言，下游不应该直接使用此结构。请参见 [`crate::driver::application_start`]
+#[derive(Parser)]
+#[grammar = "parser/asdl.pest"]
+pub struct PestASDLParser;
+
+/// 判断一个 `.asdl` 文件是否是 Zephyr ASDL 文件
+///
+/// Zephyr ASDL 文件总是以 `module` 块开头。旧版本的 RSDL 允许使用 `include a.b` 引用
+/// 以 `.asdl` 为扩展名的 RSDL 文件，这些文件仍然会被当作 RSDL 文件处理
+pub fn is_asdl_module(src: &str) -> bool {
+    src.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with("--"))
+        .and_then(|line| line.strip_prefix("module"))
+        .is_some_and(|rest| rest.starts_with(|c: char| c.is_whitespace() || c == '{'))
+}
+
+/// 将 ASDL 的 Pest AST 转换为 RSDL HIR
+///
+/// 一般而言，下游不应该直接使用此函数。请参见 [`crate::driver::application_start`]
+///
+/// # 参数
+/// - `file_name` - 文件名
+/// - `tree` - Pest AST
+/// - `defs` - （输出参数）类型定义列表
+pub fn asdlconv(file_name: &str, mut tree: Pairs<Rule>, defs: &mut Vec<TypeDef>) {
+    let asdl_program = tree
+        .next()
+        .unwrap();
+
+    assert_eq!(asdl_program.as_rule(), Rule::asdl_program);
+
+    let mut module_defs = Vec::new();
+    for module in asdl_program.into_inner() {
+        match module.as_rule() {
+            Rule::module => {
+                for item in module.into_inner() {
+                    if item.as_rule() == Rule::definition {
+                        module_defs.push(defconv(file_name, item));
+                    }
+                }
+            },
+            Rule::EOI => {},
+            _ => unreachable!()
+        }
+    }
+
+    box_recursive_fields(&mut module_defs);
+    defs.extend(module_defs);
+}
+
+/// 为可能递归地包含其所在类型的字段添加 `[boxed]` 注解
+///
+/// 类型 `A` 直接包含类型 `B`，是指 `A`（的某个构造器或者公共字段）有一个类型为 `B` 并且没有 `*` 修饰的字段。
+/// 列表本身就提供了间接性，因此 `*` 修饰的字段不会被装箱，也不会被视为直接包含
+fn box_recursive_fields(defs: &mut [TypeDef]) {
+    let mut contains: HashMap<String, Vec<String>> = HashMap::new();
+    for def in defs.iter() {
+        let (name, fields) = def_fields(def);
+        contains.insert(
+            name.to_string(),
+            fields.filter_map(|field| direct_type(field).map(ToString::to_string)).collect()
+        );
+    }
+
+    for def in defs.iter_mut() {
+        let name = def_fields(def).0.to_string();
+
+        let fields: Vec<&mut Field> = match &mut def.inner {
+            TypeDefInner::SimpleType(ctor) => ctor.fields.iter_mut().collect(),
+            TypeDefInner::SumType(sum_type) => sum_type.ctors
+                .iter_mut()
+                .flat_map(|(_, ctor)| ctor.fields.iter_mut())
+                .chain(sum_type.common_fields.iter_mut())
+                .collect(),
+            TypeDefInner::AliasType(..) => Vec::new()
+        };
+
+        for field in fields {
+            let Some(ty) = direct_type(field) else { continue; };
+            if reaches(&contains, ty, &name) {
+                field.attr.push(Attr {
+                    item: AttrItem::Identifier("boxed".to_string()),
+                    span: field.span.clone()
+                });
+            }
+        }
+    }
+}
+
+/// 类型定义的名称以及它的所有字段
+fn def_fields(def: &TypeDef) -> (&str, Box<dyn Iterator<Item = &Field> + '_>) {
+    match &def.inner {
+        TypeDefInner::SimpleType(ctor) => (&ctor.name, Box::new(ctor.fields.iter())),
+        TypeDefInner::SumType(sum_type) => (
+            &sum_type.name,
+            Box::new(sum_type.ctors
+                .iter()
+                .flat_map(|(_, ctor)| ctor.fields.iter())
+                .chain(sum_type.common_fields.iter()))
+        ),
+        TypeDefInner::AliasType(name, _) => (name, Box::new(std::iter::empty()))
+    }
+}
+
+/// 字段直接包含的类型，`*` 修饰的字段返回 `None`
+fn direct_type(field: &Field) -> Option<&str> {
+    match &field.ty {
+        RSDLType::Identifier(ident) => Some(ident),
+        _ => None
+    }
+}
+
+/// 类型 `from` 是否直接或者间接地包含类型 `to`
+fn reaches(contains: &HashMap<String, Vec<String>>, from: &str, to: &str) -> bool {
+    let mut visited = HashSet::new();
+    let mut stack = vec![from];
+    while let Some(ty) = stack.pop() {
+        if ty == to {
+            return true;
+        }
+        if !visited.insert(ty) {
+            continue;
+        }
+        if let Some(next) = contains.get(ty) {
+            stack.extend(next.iter().map(String::as_str));
+        }
+    }
+    false
+}
+
+fn defconv(file_name: &str, def: Pair<Rule>) -> TypeDef {
+    let mut iter = def.into_inner();
+    let name = iter.next().unwrap();
+    let span = Span::from_pest(file_name, name.as_span());
+    let name = name.as_str().to_string();
+
+    let body = iter.next().unwrap();
+    let inner = match body.as_rule() {
+        Rule::product_type => {
+            let mut fields = Vec::new();
+            for item in body.into_inner() {
+                fields.extend(fieldsconv(file_name, item));
+            }
+
+            TypeDefInner::SimpleType(TypeConstructor {
+                name,
+                type_params: Vec::new(),
+                fields,
+                span: span.clone()
+            })
+        },
+        Rule::sum_type => sumconv(file_name, name, body),
+        _ => unreachable!()
+    };
+
+    TypeDef {
+        file: file_name.to_string(),
+        span,
+        attr: SmallVec::new(),
+        inner
+    }
+}
+
+fn sumconv(file_name: &str, name: String, sum_type: Pair<Rule>) -> TypeDefInner {
+    let mut ctors = Vec::new();
+    let mut attributes = Vec::new();
+
+    for item in sum_type.into_inner() {
+        match item.as_rule() {
+            Rule::constructor => {
+                let mut iter = item.into_inner();
+                let ctor_name = iter.next().unwrap();
+                let ctor_span = Span::from_pest(file_name, ctor_name.as_span());
+                let fields = iter
+                    .next()
+                    .map(|fields| fieldsconv(file_name, fields))
+                    .unwrap_or_default();
+
+                ctors.push(TypeConstructor {
+                    name: ctor_name.as_str().to_string(),
+                    type_params: Vec::new(),
+                    fields,
+                    span: ctor_span
+                });
+            },
+            Rule::attributes => attributes = fieldsconv(file_name, item),
+            _ => unreachable!()
+        }
+    }
+
+    let mut scalar_variants = Vec::new();
+    let mut ctors_with_fields = Vec::new();
+    for ctor in ctors {
+        if ctor.fields.is_empty() {
+            scalar_variants.push((SmallVec::new(), ctor.name, ctor.span));
+        } else {
+            ctors_with_fields.push((SmallVec::new(), ctor));
+        }
+    }
+
+    TypeDefInner::SumType(SumType {
+        name,
+        type_params: Vec::new(),
+        scalar_variants,
+        ctors: ctors_with_fields,
+        common_fields: attributes
+    })
+}
+
+/// 转换 `fields` 或者 `attributes` 中的字段列表
+fn fieldsconv(file_name: &str, fields: Pair<Rule>) -> Vec<Field> {
+    let fields = match fields.as_rule() {
+        Rule::fields => fields,
+        Rule::attributes => fields.into_inner().nth(1).unwrap(),
+        _ => unreachable!()
+    };
+
+    fields
+        .into_inner()
+        .map(|field| fieldconv(file_name, field))
+        .collect()
+}
+
+fn fieldconv(file_name: &str, field: Pair<Rule>) -> Field {
+    let mut iter = field.into_inner();
+    let ty = iter.next().unwrap();
+    let ty_name = ty.as_str();
+
+    let mut qualifier = None;
+    let mut name = ty.clone();
+    for item in iter {
+        match item.as_rule() {
+            Rule::qualifier => qualifier = Some(item.as_str()),
+            Rule::identifier => name = item,
+            _ => unreachable!()
+        }
+    }
+
+    let ty = RSDLType::Identifier(builtin_type(ty_name).to_string());
+    let (optionality, ty) = match qualifier {
+        Some("*") => (Optionality::Required, RSDLType::List(Box::new(ty))),
+        Some("?") => (Optionality::Optional, ty),
+        _ => (Optionality::Required, ty)
+    };
+
+    Field {
+        attr: SmallVec::new(),
+        optionality,
+        ty,
+        name: name.as_str().to_string(),
+        span: Span::from_pest(file_name, name.as_span()),
+        default: None
+    }
+}
+
+/// 将 ASDL 的内置类型映射为 RSDL 标准库中的类型
+fn builtin_type(ty_name: &str) -> &str {
+    match ty_name {
+        "identifier" | "string" => "str",
+        _ => ty_name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::testing::compile_files;
+
+    /// 使用 example 目录中 CPython 的 Python.asdl 生成的 Rust 代码，这个模块能通过编译说明生成的代码是合法的
+    #[allow(non_camel_case_types, dead_code, clippy::enum_variant_names)]
+    mod python_ast {
+        include!("../../example/python.rs");
+    }
+
+    fn compile_python(target: &str) -> String {
+        compile_files(&[
+            ("example/python.rsdl", include_str!("../../example/python.rsdl")),
+            ("example/Python.asdl", include_str!("../../example/Python.asdl"))
+        ], target).unwrap_or_else(|diags| panic!("编译失败:\n{}", diags))
+    }
+
+    #[test]
+    fn python_asdl_examples_are_current() {
+        assert_eq!(compile_python("rust"), include_str!("../../example/python.rs"));
+        assert_eq!(compile_python("typescript"), include_str!("../../example/python.ts"));
+    }
+
+    #[test]
+    fn only_recursive_fields_are_boxed() {
+        let code = compile_python("rust");
+        assert!(code.contains("pub struct BinOp {\n    pub left: Box<expr>,\n    pub op: operator,\n    pub right: Box<expr>,"), "{}", code);
+        assert!(code.contains("pub struct Return {\n    pub value: Option<expr>,"), "{}", code);
+
+        let node = python_ast::expr::UnaryOp(python_ast::UnaryOp {
+            op: python_ast::unaryop::Not,
+            operand: Box::new(python_ast::expr::Name(python_ast::Name {
+                id: "x".to_string(),
+                ctx: python_ast::expr_context::Load,
+                lineno: 1,
+                col_offset: 4,
+                end_lineno: None,
+                end_col_offset: None
+            })),
+            lineno: 1,
+            col_offset: 0,
+            end_lineno: None,
+            end_col_offset: None
+        });
+        assert!(format!("{:?}", node).starts_with("UnaryOp"));
+    }
+}
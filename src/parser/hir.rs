@@ -4,6 +4,8 @@ use std::collections::HashMap;
 use std::error::Error;
 use smallvec::SmallVec;
 
+use crate::diagnostic::{Diagnostic, Span};
+
 #[cfg(feature = "serde")]
 use serde::{Serialize, Deserialize};
 
@@ -27,6 +29,38 @@ pub enum AttrItem {
     /// ```
     String(String),
 
+    /// 整数字面量
+    ///
+    /// # 示例
+    /// ```rsdl
+    /// [tag(3)]
+    /// ```
+    Integer(i64),
+
+    /// 浮点数字面量
+    ///
+    /// # 示例
+    /// ```rsdl
+    /// [scale = 0.5]
+    /// ```
+    Float(f64),
+
+    /// 布尔字面量
+    ///
+    /// # 示例
+    /// ```rsdl
+    /// [default = true]
+    /// ```
+    Bool(bool),
+
+    /// 列表
+    ///
+    /// # 示例
+    /// ```rsdl
+    /// [allowed = ["a", "b", 3]]
+    /// ```
+    List(Vec<AttrItem>),
+
     /// “赋值”形式的注解
     ///
     /// # 示例
@@ -53,6 +87,32 @@ pub enum AttrItem {
     CallAlike(String, Vec<AttrItem>)
 }
 
+impl AttrItem {
+    /// 注解项的名称，即标识符本身、赋值目标或者被调用的名称。字面量和列表没有名称
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            AttrItem::Identifier(name)
+            | AttrItem::Assignment(name, _)
+            | AttrItem::CallAlike(name, _) => Some(name),
+            AttrItem::String(_)
+            | AttrItem::Integer(_)
+            | AttrItem::Float(_)
+            | AttrItem::Bool(_)
+            | AttrItem::List(_) => None
+        }
+    }
+}
+
+/// 一个顶层注解，即写在 `[...]` 或者 `[[...]]` 中的注解项，以及它在源代码中的位置
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Attr {
+    /// 注解项
+    pub item: AttrItem,
+    /// 注解项所在的位置
+    pub span: Span
+}
+
 /// 一个 RSDL 类型
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -81,13 +141,162 @@ pub enum RSDLType {
     /// [int] -- 与上面的写法等价
     /// ```
     List(Box<RSDLType>),
-    /// 记录类型
+    /// 定长数组类型，长度必须是正整数，由 [`crate::min_resolv::ResolveContext`] 检查
+    ///
+    /// # 示例
+    /// ```rsdl
+    /// [u8; 16]
+    /// ```
+    Array(Box<RSDLType>, usize),
+    /// 元组类型，至少包含两个元素
     ///
     /// # 示例
     /// ```rsdl
-    /// record<str, SomeType>
+    /// (int, str)
     /// ```
-    Record(Box<RSDLType>)
+    Tuple(Vec<RSDLType>),
+    /// 映射类型，元组中的两个元素分别是键类型和值类型
+    ///
+    /// 键类型只能是 `str`、`int`、只包含标量变体的和类型、native 类型或者它们的别名，
+    /// 由 [`crate::min_resolv::ResolveContext`] 检查
+    ///
+    /// # 示例
+    /// ```rsdl
+    /// map<int, SomeType>
+    /// record<str, SomeType> -- 等价于 map<str, SomeType>
+    /// ```
+    Map(Box<RSDLType>, Box<RSDLType>),
+    /// 集合类型，元素类型的限制与映射类型的键类型相同
+    ///
+    /// # 示例
+    /// ```rsdl
+    /// set<str>
+    /// ```
+    Set(Box<RSDLType>),
+    /// 泛型类型的实例
+    ///
+    /// 元组中的两个元素分别是泛型类型的名称和类型实参
+    ///
+    /// # 示例
+    /// ```rsdl
+    /// Spanned<Expr>
+    /// ```
+    Generic(String, Vec<RSDLType>)
+}
+
+impl RSDLType {
+    /// 类型中是否引用了名为 `name` 的类型（或者类型参数）
+    pub fn mentions(&self, name: &str) -> bool {
+        match self {
+            RSDLType::Identifier(ident) => ident == name,
+            RSDLType::Native(_) => false,
+            RSDLType::List(inner) | RSDLType::Set(inner) | RSDLType::Array(inner, _) => inner.mentions(name),
+            RSDLType::Map(key, value) => key.mentions(name) || value.mentions(name),
+            RSDLType::Tuple(items) => items.iter().any(|item| item.mentions(name)),
+            RSDLType::Generic(ident, args) => ident == name || args.iter().any(|arg| arg.mentions(name))
+        }
+    }
+}
+
+/// 计算 `type_params` 中被 `fields` 使用的类型参数，顺序与 `type_params` 相同
+///
+/// 和类型的构造器只需要声明它使用到的类型参数，例如 `Result<T, E>` 的构造器 `Ok(value: T)`
+/// 对应的类型是 `Ok<T>`
+pub fn used_type_params<'a, 'b>(
+    type_params: &'a [String],
+    fields: impl IntoIterator<Item = &'b Field> + Clone
+) -> Vec<&'a str> {
+    type_params
+        .iter()
+        .filter(|param| fields.clone().into_iter().any(|field| field.ty.mentions(param)))
+        .map(String::as_str)
+        .collect()
+}
+
+/// 字段默认值中的字面量
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Literal {
+    /// 整数字面量，例如 `0`
+    Integer(i64),
+    /// 浮点数字面量，例如 `0.5`
+    Float(f64),
+    /// 布尔字面量，`true` 或者 `false`
+    Bool(bool),
+    /// 字符串字面量，例如 `"anon"`
+    String(String),
+    /// 和类型的标量变体，例如 `Fast`
+    Variant(String),
+    /// 列表字面量，例如 `[1, 2, 3]`
+    List(Vec<Literal>)
+}
+
+/// 字段的默认值
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DefaultValue {
+    /// 默认值字面量
+    pub value: Literal,
+    /// 默认值所在的位置
+    pub span: Span
+}
+
+/// 字段的可选性，决定字段在 JSON 中是否可以缺失以及是否可以为 `null`
+///
+/// # 示例
+/// ```rsdl
+/// a: int         -- Required
+/// b?: int        -- Optional
+/// c: int | null  -- Nullable
+/// d?: int | null -- OptionalNullable
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Optionality {
+    /// 字段必须存在，并且不能为 `null`
+    Required,
+    /// 字段可以缺失，但是存在时不能为 `null`
+    Optional,
+    /// 字段必须存在，但是可以为 `null`
+    Nullable,
+    /// 字段可以缺失，也可以为 `null`
+    OptionalNullable
+}
+
+impl Optionality {
+    /// 字段是否可以缺失
+    pub fn is_optional(self) -> bool {
+        matches!(self, Optionality::Optional | Optionality::OptionalNullable)
+    }
+
+    /// 字段是否可以为 `null`
+    pub fn is_nullable(self) -> bool {
+        matches!(self, Optionality::Nullable | Optionality::OptionalNullable)
+    }
+}
+
+/// 类型构造器中的一个字段
+///
+/// # 示例
+/// ```rsdl
+/// [doc = "字段的注解"]
+/// name?: str = "anon"
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Field {
+    /// 字段的注解
+    pub attr: SmallVec<[Attr; 2]>,
+    /// 字段是否可以缺失以及是否可以为 `null`
+    pub optionality: Optionality,
+    /// 字段的类型
+    pub ty: RSDLType,
+    /// 字段的名称
+    pub name: String,
+    /// 字段名称所在的位置
+    pub span: Span,
+    /// 字段的默认值，类型检查由 [`crate::min_resolv::ResolveContext`] 完成
+    pub default: Option<DefaultValue>
 }
 
 /// 一个 RSDL 类型构造器
@@ -102,25 +311,31 @@ pub enum RSDLType {
 ///     [boxed] c: SomeType
 ///     d: list<SomeType>
 /// )
+///
+/// Spanned<T>(node: T, span: Span)
+/// ```
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TypeConstructor {
     /// 构造器的名称
     pub name: String,
 
-    /// 构造器的字段列表
+    /// 类型参数
     ///
-    /// 元组中的四个元素分别是：
-    /// - 字段的注解
-    /// - 字段是否可空
-    /// - 字段的类型
-    /// - 字段的名称
-    pub fields: Vec<(SmallVec<[AttrItem; 2]>, bool, RSDLType, String)>
+    /// 只有简单类型可以声明类型参数，和类型的构造器使用和类型的类型参数，
+    /// 参见 [`used_type_params`]
+    pub type_params: Vec<String>,
+
+    /// 构造器的字段列表
+    pub fields: Vec<Field>,
+
+    /// 构造器名称所在的位置
+    pub span: Span
 }
 
 /// 一个 RSDL 和类型
 ///
-/// 一个和类型中有多个构造器和多个标量变体
+/// 一个和类型中有多个构造器和多个标量变体，以及所有构造器和标量变体共有的公共字段
 ///
 /// # 示例
 ///
@@ -129,21 +344,36 @@ pub struct TypeConstructor {
 ///          | ISO8601String(iso8601: str)
 ///          | RFC3339Elaborated(year: int, month: int, day: int, hour: int, minute: int, second: float, timezone: int)
 ///          | UnknownDateTime
+///
+/// Expr : BinOp(op: str, [boxed] lhs: Expr, [boxed] rhs: Expr)
+///      | Ident(name: str)
+///      attributes (line: int, col: int)
+///
+/// Result<T, E> : Ok(value: T) | Err(error: E)
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SumType {
     /// 和类型的名称
     pub name: String,
 
+    /// 类型参数
+    pub type_params: Vec<String>,
+
     /// 标量变体
     ///
-    /// 元组中的两个元素分别是：
+    /// 元组中的三个元素分别是：
     /// - 变体的注解
     /// - 变体的名称
-    pub scalar_variants: Vec<(SmallVec<[AttrItem; 2]>, String)>,
+    /// - 变体名称所在的位置
+    pub scalar_variants: Vec<(SmallVec<[Attr; 2]>, String, Span)>,
 
     /// 构造器
-    pub ctors: Vec<(SmallVec<[AttrItem; 2]>, TypeConstructor)>
+    pub ctors: Vec<(SmallVec<[Attr; 2]>, TypeConstructor)>,
+
+    /// 公共字段，即 `attributes (...)` 中的字段
+    ///
+    /// 公共字段属于和类型的每一个构造器和标量变体，如何表示公共字段由代码生成器决定
+    pub common_fields: Vec<Field>
 }
 
 /// 一个 RSDL 类型定义的“内容”
@@ -164,31 +394,189 @@ pub enum TypeDefInner {
 pub struct TypeDef {
     /// 类型定义所在的文件
     pub file: String,
+    /// 类型名称所在的位置
+    pub span: Span,
     /// 类型定义的注解
-    pub attr: SmallVec<[AttrItem; 2]>,
+    pub attr: SmallVec<[Attr; 2]>,
     /// 实际的类型定义内容
     pub inner: TypeDefInner
 }
 
-/// 检查一个注解列表中是否包含某个标识符注解
-pub fn check_ident_attr(attr_list: &[AttrItem], checked_ident: &str) -> bool {
-    for attr in attr_list {
-        if let AttrItem::Identifier(ident) = attr {
-            if ident == checked_ident {
-                return true;
+/// 查询注解列表的接口
+///
+/// 这个 `trait` 为 `[Attr]`（以及可以解引用为 `[Attr]` 的 `SmallVec<[Attr; 2]>` 等类型）实现，
+/// 代码生成器应该使用这些方法读取注解，而不是自行匹配 [`AttrItem`]。
+/// 当注解的形式不正确时，这些方法会返回带有注解位置的 [`Diagnostic`]
+///
+/// # 示例
+///
+/// ```rsdl
+/// [rust_derive(Debug, Clone)]
+/// [rust_attr(repr(C))]
+/// [doc = "一个点"]
+/// [boxed]
+/// ```
+///
+/// ```rust
+/// use pest::Parser;
+/// use rsdl::parser::hir::AttrQuery;
+/// use rsdl::parser::pest_parser::{PestRSDLParser, Rule};
+/// use rsdl::parser::treeconv::treeconv;
+///
+/// let src = "[rust_derive(Debug, Clone)]\n[rust_attr(repr(C))]\n[doc = \"一个点\"]\n[boxed]\nPoint(x: int)";
+/// let mut defs = Vec::new();
+/// treeconv("point.rsdl", PestRSDLParser::parse(Rule::rsdl_program, src).unwrap(), &mut Vec::new(), &mut defs);
+/// let attrs = &defs[0].attr;
+///
+/// assert!(attrs.flag("boxed"));
+/// assert_eq!(attrs.ident_args("rust_derive").unwrap(), vec!["Debug", "Clone"]);
+/// assert_eq!(attrs.call_args("rust_attr").unwrap().len(), 1);
+/// assert_eq!(attrs.string_value("doc").unwrap(), Some("一个点"));
+/// assert!(attrs.string_value("boxed").is_err());
+/// ```
+pub trait AttrQuery {
+    /// 所有名为 `name` 的注解，无论其形式
+    fn all(&self, name: &str) -> Vec<&Attr>;
+
+    /// 是否存在名为 `name` 的标识符注解，例如 `[boxed]`
+    fn flag(&self, name: &str) -> bool;
+
+    /// 所有名为 `name` 的调用形式注解的参数，例如 `[rust_attr(a, b)] [rust_attr(c)]` 的 `a, b, c`
+    ///
+    /// 如果存在同名但不是调用形式的注解，返回错误
+    fn call_args(&self, name: &str) -> Result<Vec<&AttrItem>, Diagnostic>;
+
+    /// 与 [`AttrQuery::call_args`] 相同，但是要求所有的参数都是标识符
+    fn ident_args(&self, name: &str) -> Result<Vec<&str>, Diagnostic>;
+
+    /// 所有名为 `name` 的字符串注解的值，注解可以写作 `[name("...")]` 或者 `[name = "..."]`
+    ///
+    /// 如果存在同名但形式不同的注解，返回错误
+    fn string_values(&self, name: &str) -> Result<Vec<&str>, Diagnostic>;
+
+    /// 名为 `name` 的字符串注解的值，参见 [`AttrQuery::string_values`]
+    ///
+    /// 如果这个注解出现了不止一次，返回错误
+    fn string_value(&self, name: &str) -> Result<Option<&str>, Diagnostic>;
+}
+
+impl AttrQuery for [Attr] {
+    fn all(&self, name: &str) -> Vec<&Attr> {
+        self.iter()
+            .filter(|attr| attr.item.name() == Some(name))
+            .collect()
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.iter().any(|attr| matches!(&attr.item, AttrItem::Identifier(ident) if ident == name))
+    }
+
+    fn call_args(&self, name: &str) -> Result<Vec<&AttrItem>, Diagnostic> {
+        let mut ret = Vec::new();
+        for attr in self.all(name) {
+            if let AttrItem::CallAlike(_, args) = &attr.item {
+                ret.extend(args.iter());
+            } else {
+                return Err(Diagnostic::new(attr.span.clone(), format!(
+                    "{} 注解应该写作 {}(...) 的形式",
+                    name,
+                    name
+                )));
+            }
+        }
+        Ok(ret)
+    }
+
+    fn ident_args(&self, name: &str) -> Result<Vec<&str>, Diagnostic> {
+        let mut ret = Vec::new();
+        for attr in self.all(name) {
+            let AttrItem::CallAlike(_, args) = &attr.item else {
+                return Err(Diagnostic::new(attr.span.clone(), format!(
+                    "{} 注解应该写作 {}(...) 的形式",
+                    name,
+                    name
+                )));
+            };
+
+            for arg in args {
+                let AttrItem::Identifier(ident) = arg else {
+                    return Err(Diagnostic::new(attr.span.clone(), format!(
+                        "{} 注解的参数必须是标识符",
+                        name
+                    )));
+                };
+                ret.push(ident.as_str());
+            }
+        }
+        Ok(ret)
+    }
+
+    fn string_values(&self, name: &str) -> Result<Vec<&str>, Diagnostic> {
+        let mut ret = Vec::new();
+        for attr in self.all(name) {
+            match &attr.item {
+                AttrItem::CallAlike(_, args) => {
+                    if args.len() != 1 {
+                        return Err(Diagnostic::new(attr.span.clone(), format!(
+                            "{} 注解的参数数量必须为 1，但是此处有 {} 个参数",
+                            name,
+                            args.len()
+                        )));
+                    }
+
+                    let AttrItem::String(value) = &args[0] else {
+                        return Err(Diagnostic::new(attr.span.clone(), format!(
+                            "{} 注解的参数必须是字符串字面量",
+                            name
+                        )));
+                    };
+                    ret.push(value.as_str());
+                },
+                AttrItem::Assignment(_, value) => {
+                    let AttrItem::String(value) = value.as_ref() else {
+                        return Err(Diagnostic::new(attr.span.clone(), format!(
+                            "{} 注解的值必须是字符串字面量",
+                            name
+                        )));
+                    };
+                    ret.push(value.as_str());
+                },
+                _ => return Err(Diagnostic::new(attr.span.clone(), format!(
+                    "{} 注解应该写作 {}(\"...\") 或者 {} = \"...\" 的形式",
+                    name,
+                    name,
+                    name
+                )))
             }
         }
+        Ok(ret)
     }
-    false
+
+    fn string_value(&self, name: &str) -> Result<Option<&str>, Diagnostic> {
+        let values = self.string_values(name)?;
+        if values.len() > 1 {
+            let attr = self.all(name)[1];
+            return Err(Diagnostic::new(attr.span.clone(), format!(
+                "{} 注解只能出现一次",
+                name
+            )));
+        }
+        Ok(values.first().copied())
+    }
+}
+
+/// 检查一个注解列表中是否包含某个标识符注解
+pub fn check_ident_attr(attr_list: &[Attr], checked_ident: &str) -> bool {
+    attr_list.flag(checked_ident)
 }
 
 /// 检查一个注解列表中是否包含 `private` 注解
-pub fn check_private(attr_list: &[AttrItem]) -> bool {
+pub fn check_private(attr_list: &[Attr]) -> bool {
     check_ident_attr(attr_list, "private")
 }
 
 /// 检查一个注解列表中是否包含 `boxed` 注解
-pub fn check_boxed(attr_list: &[AttrItem]) -> bool {
+pub fn check_boxed(attr_list: &[Attr]) -> bool {
     check_ident_attr(attr_list, "boxed")
 }
 
@@ -203,63 +591,23 @@ pub fn check_boxed(attr_list: &[AttrItem]) -> bool {
 ///
 /// 提取出来的文档字符串是：
 /// ```rust,no_run
+/// # let _: Vec<String> =
 /// vec![
 ///     "这是一个文档字符串".to_string(),
 ///     "这是另一个文档字符串".to_string(),
 ///     "这个文档字符串有两行".to_string()
 /// ]
+/// # ;
 /// ```
 pub fn extract_doc_strings(
-    attr_list: &[AttrItem],
+    attr_list: &[Attr],
     doc_attr_name: &str,
 ) -> Result<Vec<String>, Box<dyn Error>> {
     let mut ret = Vec::new();
 
-    fn add_doc_string(output: &mut Vec<String>, doc_string: &str) {
-        if doc_string.contains("\n") {
-            for line in doc_string.split("\n") {
-                output.push(line.trim().to_string());
-            }
-        } else {
-            output.push(doc_string.trim().to_string());
-        }
-    }
-
-    for attr in attr_list {
-        match attr {
-            AttrItem::CallAlike(fn_alike, param_alike) => {
-                if fn_alike == doc_attr_name {
-                    if param_alike.len() != 1 {
-                        return Err(format!(
-                            "{} 属性的参数数量必须为 1，但是此处有 {} 个参数",
-                            doc_attr_name,
-                            param_alike.len()
-                        ).into());
-                    }
-
-                    if let AttrItem::String(doc) = &param_alike[0] {
-                        add_doc_string(&mut ret, doc);
-                    } else {
-                        return Err(format!(
-                            "{} 属性的参数必须是字符串字面量",
-                            doc_attr_name
-                        ).into());
-                    }
-                }
-            },
-            AttrItem::Assignment(assignee, value) => {
-                if assignee == doc_attr_name {
-                    if let AttrItem::String(doc) = value.as_ref() {
-                        add_doc_string(&mut ret, &doc);
-                    } else {
-                        return Err(format!(
-                            "{} 属性的值必须是字符串字面量",
-                            doc_attr_name
-                        ).into());
-                    }
-                }
-            },
-            _ => {}
+    for doc_string in attr_list.string_values(doc_attr_name)? {
+        for line in doc_string.split('\n') {
+            ret.push(line.trim().to_string());
         }
     }
 
@@ -267,6 +615,6 @@ pub fn extract_doc_strings(
 }
 
 /// 检查一个注解列表中是否包含 `inline` 注解
-pub fn check_inline(attr_list: &[AttrItem]) -> bool {
+pub fn check_inline(attr_list: &[Attr]) -> bool {
     check_ident_attr(attr_list, "inline")
 }
@@ -1,5 +1,6 @@
 //! `rsdl` 的解析器模块
 
+pub mod asdl;
 pub mod hir;
 pub mod pest_parser;
 pub mod treeconv;
@@ -4,8 +4,14 @@ use std::collections::HashMap;
 use pest::iterators::{Pair, Pairs};
 use smallvec::SmallVec;
 
+use crate::diagnostic::Span;
 use crate::parser::hir::{
+    Attr,
     AttrItem,
+    DefaultValue,
+    Field,
+    Literal,
+    Optionality,
     RSDLType,
     SumType,
     TypeConstructor,
@@ -28,7 +34,7 @@ use crate::parser::pest_parser::Rule;
 /// # 参见
 /// - [`crate::parser::pest_parser::PestRSDLParser`] - Pest 解析器
 /// - [`crate::parser::hir`] - RSDL HIR
-pub fn treeconv(file_name: &str, mut tree: Pairs<Rule>, global_attr: &mut Vec<AttrItem>, defs: &mut Vec<TypeDef>) {
+pub fn treeconv(file_name: &str, mut tree: Pairs<Rule>, global_attr: &mut Vec<Attr>, defs: &mut Vec<TypeDef>) {
     let rsdl_program = tree
         .next()
         .unwrap();
@@ -38,7 +44,9 @@ pub fn treeconv(file_name: &str, mut tree: Pairs<Rule>, global_attr: &mut Vec<At
     for rsdl_item in rsdl_program.into_inner() {
         match rsdl_item.as_rule() {
             Rule::type_def => defs.push(tydeconv(file_name, rsdl_item)),
-            Rule::global_attr => global_attr.push(attrconv(rsdl_item)),
+            Rule::global_attr => global_attr.push(attrconv(file_name, rsdl_item)),
+            // 不属于任何定义的文档注释，忽略
+            Rule::doc_comment => {},
             Rule::EOI => {},
             _ => unreachable!()
         }
@@ -46,17 +54,18 @@ pub fn treeconv(file_name: &str, mut tree: Pairs<Rule>, global_attr: &mut Vec<At
 }
 
 fn tydeconv(file_name: &str, ty: Pair<Rule>) -> TypeDef {
-    let mut attr: SmallVec<[AttrItem; 2]> = SmallVec::new();
+    let mut attr: SmallVec<[Attr; 2]> = SmallVec::new();
 
     for tyde_item in ty.into_inner() {
         match tyde_item.as_rule() {
-            Rule::attr => { attr.push(attrconv(tyde_item)); },
+            Rule::attr => { attr.push(attrconv(file_name, tyde_item)); },
             Rule::type_alias => { return convtypealias(file_name, attr, tyde_item); },
             Rule::sum_type => { return convsumtype(file_name, attr, tyde_item); }
             Rule::type_ctor => {
-                let ctor = ctorconv(tyde_item);
+                let ctor = ctorconv(file_name, tyde_item);
                 return TypeDef {
                     file: file_name.to_string(),
+                    span: ctor.span.clone(),
                     attr,
                     inner: TypeDefInner::SimpleType(ctor)
                 }
@@ -71,18 +80,54 @@ fn tydeconv(file_name: &str, ty: Pair<Rule>) -> TypeDef {
     unreachable!()
 }
 
-fn attrconv(attr: Pair<Rule>) -> AttrItem {
+fn attrconv(file_name: &str, attr: Pair<Rule>) -> Attr {
     let attr_item = attr.into_inner().next().unwrap();
+    if attr_item.as_rule() == Rule::doc_comment {
+        return doccommentconv(file_name, attr_item);
+    }
     assert_eq!(attr_item.as_rule(), Rule::attr_item);
 
+    let span = Span::from_pest(file_name, attr_item.as_span());
     let attr_item_inner = attr_item.into_inner().next().unwrap();
-    imp_attrconv(attr_item_inner)
+    Attr {
+        item: imp_attrconv(attr_item_inner),
+        span
+    }
+}
+
+/// 将 `--- text` 形式的文档注释转换为 `[doc = "text"]` 注解
+fn doccommentconv(file_name: &str, doc_comment: Pair<Rule>) -> Attr {
+    let span = Span::from_pest(file_name, doc_comment.as_span());
+    let text = doc_comment.into_inner().next().unwrap();
+    assert_eq!(text.as_rule(), Rule::doc_comment_text);
+
+    let text = text.as_str().trim_end_matches('\r');
+    let text = text.strip_prefix(' ').unwrap_or(text);
+
+    Attr {
+        item: AttrItem::Assignment(
+            "doc".to_string(),
+            Box::new(AttrItem::String(text.to_string()))
+        ),
+        span
+    }
 }
 
 fn imp_attrconv(inner: Pair<Rule>) -> AttrItem {
     match inner.as_rule() {
         Rule::identifier => AttrItem::Identifier(inner.as_str().to_string()),
         Rule::string => AttrItem::String(strchkconv(inner)),
+        Rule::integer => AttrItem::Integer(inner.as_str().parse().unwrap()),
+        Rule::float => AttrItem::Float(inner.as_str().parse().unwrap()),
+        Rule::boolean => AttrItem::Bool(inner.as_str() == "true"),
+        Rule::list_attr => {
+            let items = match inner.into_inner().next() {
+                Some(attr_item_list) => attritemlistconv(attr_item_list),
+                None => Vec::new()
+            };
+
+            AttrItem::List(items)
+        },
         Rule::assign_attr => {
             let mut iter = inner.into_inner();
             let identifier = identchkconv(iter.next().unwrap());
@@ -93,18 +138,7 @@ fn imp_attrconv(inner: Pair<Rule>) -> AttrItem {
         Rule::call_attr => {
             let mut iter = inner.into_inner();
             let identifier = identchkconv(iter.next().unwrap());
-            let attr_item_list = iter.next().unwrap();
-            assert_eq!(attr_item_list.as_rule(), Rule::attr_item_list);
-            let call_args = attr_item_list
-                .into_inner()
-                .map(|attr_item| {
-                    let attr_item_inner = attr_item
-                        .into_inner()
-                        .next()
-                        .unwrap();
-                    imp_attrconv(attr_item_inner)
-                })
-                .collect::<Vec<_>>();
+            let call_args = attritemlistconv(iter.next().unwrap());
 
             AttrItem::CallAlike(identifier, call_args)
         },
@@ -112,17 +146,34 @@ fn imp_attrconv(inner: Pair<Rule>) -> AttrItem {
     }
 }
 
+fn attritemlistconv(attr_item_list: Pair<Rule>) -> Vec<AttrItem> {
+    assert_eq!(attr_item_list.as_rule(), Rule::attr_item_list);
+    attr_item_list
+        .into_inner()
+        .map(|attr_item| {
+            let attr_item_inner = attr_item
+                .into_inner()
+                .next()
+                .unwrap();
+            imp_attrconv(attr_item_inner)
+        })
+        .collect()
+}
+
 fn convtypealias(
     file_name: &str,
-    attr: SmallVec<[AttrItem; 2]>,
+    attr: SmallVec<[Attr; 2]>,
     alias: Pair<Rule>
 ) -> TypeDef {
     let mut iter = alias.into_inner();
-    let identifier = identchkconv(iter.next().unwrap());
+    let identifier = iter.next().unwrap();
+    let span = Span::from_pest(file_name, identifier.as_span());
+    let identifier = identchkconv(identifier);
     let rsdl_type = convrsdltype(iter.next().unwrap());
 
     TypeDef {
         file: file_name.to_string(),
+        span,
         attr,
         inner: TypeDefInner::AliasType(identifier, rsdl_type)
     }
@@ -130,54 +181,90 @@ fn convtypealias(
 
 fn convsumtype(
     file_name: &str,
-    attr: SmallVec<[AttrItem; 2]>,
+    attr: SmallVec<[Attr; 2]>,
     sumtype: Pair<Rule>
 ) -> TypeDef {
     let mut iter = sumtype.into_inner();
-    let name = identchkconv(iter.next().unwrap());
+    let name = iter.next().unwrap();
+    let span = Span::from_pest(file_name, name.as_span());
+    let name = identchkconv(name);
 
     let mut scalar_variants = Vec::new();
     let mut ctors = Vec::new();
-    let variant_list = iter.next().unwrap();
+    let mut variant_list = iter.next().unwrap();
+    let type_params = if variant_list.as_rule() == Rule::type_params {
+        let type_params = typeparamsconv(variant_list);
+        variant_list = iter.next().unwrap();
+        type_params
+    } else {
+        Vec::new()
+    };
     assert_eq!(variant_list.as_rule(), Rule::variant_list);
 
-    for variant in variant_list.into_inner() {
-        variantconv(variant, &mut scalar_variants, &mut ctors);
+    // 写在 | 之前的文档注释属于 | 之后的变体
+    let mut sep_docs = SmallVec::new();
+    for item in variant_list.into_inner() {
+        match item.as_rule() {
+            Rule::variant_sep => sep_docs.extend(
+                item.into_inner().map(|doc_comment| doccommentconv(file_name, doc_comment))
+            ),
+            Rule::sum_type_variant => variantconv(
+                file_name,
+                std::mem::take(&mut sep_docs),
+                item,
+                &mut scalar_variants,
+                &mut ctors
+            ),
+            _ => unreachable!()
+        }
     }
 
+    let common_fields = match iter.next() {
+        Some(common_fields) => {
+            assert_eq!(common_fields.as_rule(), Rule::common_fields);
+            fldlistconv(file_name, common_fields.into_inner().next().unwrap())
+        },
+        None => Vec::new()
+    };
+
     TypeDef {
         file: file_name.to_string(),
+        span,
         attr,
         inner: TypeDefInner::SumType(SumType {
             name,
+            type_params,
             scalar_variants,
-            ctors
+            ctors,
+            common_fields
         })
     }
 }
 
 fn variantconv(
+    file_name: &str,
+    mut attr: SmallVec<[Attr; 2]>,
     variant: Pair<Rule>,
-    scalar_variants: &mut Vec<(SmallVec<[AttrItem; 2]>, String)>,
-    ctors: &mut Vec<(SmallVec<[AttrItem; 2]>, TypeConstructor)>
+    scalar_variants: &mut Vec<(SmallVec<[Attr; 2]>, String, Span)>,
+    ctors: &mut Vec<(SmallVec<[Attr; 2]>, TypeConstructor)>
 ) {
     let iter = variant.into_inner();
-    let mut attr = SmallVec::new();
 
     for variant_item in iter {
         match variant_item.as_rule() {
-            Rule::attr => { attr.push(attrconv(variant_item)); },
+            Rule::attr => { attr.push(attrconv(file_name, variant_item)); },
             Rule::identifier => {
                 scalar_variants.push((
                     attr,
-                    variant_item.as_str().to_string()
+                    variant_item.as_str().to_string(),
+                    Span::from_pest(file_name, variant_item.as_span())
                 ));
                 return;
             },
             Rule::type_ctor => {
                 ctors.push((
                     attr,
-                    ctorconv(variant_item)
+                    ctorconv(file_name, variant_item)
                 ));
                 return;
             },
@@ -186,27 +273,50 @@ fn variantconv(
     }
 }
 
-fn ctorconv(ctor: Pair<Rule>) -> TypeConstructor {
+fn ctorconv(file_name: &str, ctor: Pair<Rule>) -> TypeConstructor {
     let mut iter = ctor.into_inner();
-    let name = identchkconv(iter.next().unwrap());
-    let field_list = iter.next().unwrap();
-    assert_eq!(field_list.as_rule(), Rule::field_list);
+    let name = iter.next().unwrap();
+    let span = Span::from_pest(file_name, name.as_span());
+    let name = identchkconv(name);
+
+    let mut item = iter.next().unwrap();
+    let type_params = if item.as_rule() == Rule::type_params {
+        let type_params = typeparamsconv(item);
+        item = iter.next().unwrap();
+        type_params
+    } else {
+        Vec::new()
+    };
+    let fields = fldlistconv(file_name, item);
+
+    TypeConstructor { name, type_params, fields, span }
+}
 
-    let fields = field_list.into_inner().map(fldconv).collect::<Vec<_>>();
+fn typeparamsconv(type_params: Pair<Rule>) -> Vec<String> {
+    assert_eq!(type_params.as_rule(), Rule::type_params);
+    type_params.into_inner().map(identchkconv).collect()
+}
+
+fn fldlistconv(file_name: &str, field_list: Pair<Rule>) -> Vec<Field> {
+    assert_eq!(field_list.as_rule(), Rule::field_list);
 
-    TypeConstructor { name, fields }
+    field_list
+        .into_inner()
+        .map(|fld| fldconv(file_name, fld))
+        .collect()
 }
 
-fn fldconv(fld: Pair<Rule>) -> (SmallVec<[AttrItem; 2]>, bool, RSDLType, String) {
+fn fldconv(file_name: &str, fld: Pair<Rule>) -> Field {
     let mut attr = SmallVec::new();
     let mut iter = fld.into_inner();
 
     let mut item = iter.next().unwrap();
     while item.as_rule() == Rule::attr {
-        attr.push(attrconv(item));
+        attr.push(attrconv(file_name, item));
         item = iter.next().unwrap();
     }
 
+    let span = Span::from_pest(file_name, item.as_span());
     let ident = identchkconv(item);
 
     let optional_mark = iter.next().unwrap();
@@ -215,7 +325,48 @@ fn fldconv(fld: Pair<Rule>) -> (SmallVec<[AttrItem; 2]>, bool, RSDLType, String)
 
     let rsdl_type = convrsdltype(iter.next().unwrap());
 
-    (attr, is_optional, rsdl_type, ident)
+    let mut iter = iter.peekable();
+    let is_nullable = iter.next_if(|item| item.as_rule() == Rule::nullable_mark).is_some();
+    let optionality = match (is_optional, is_nullable) {
+        (false, false) => Optionality::Required,
+        (true, false) => Optionality::Optional,
+        (false, true) => Optionality::Nullable,
+        (true, true) => Optionality::OptionalNullable
+    };
+
+    let default = iter.next().map(|field_default| {
+        assert_eq!(field_default.as_rule(), Rule::field_default);
+        let literal = field_default.into_inner().next().unwrap();
+
+        DefaultValue {
+            span: Span::from_pest(file_name, literal.as_span()),
+            value: literalconv(literal)
+        }
+    });
+
+    Field {
+        attr,
+        optionality,
+        ty: rsdl_type,
+        name: ident,
+        span,
+        default
+    }
+}
+
+fn literalconv(literal: Pair<Rule>) -> Literal {
+    assert_eq!(literal.as_rule(), Rule::literal);
+    let inner = literal.into_inner().next().unwrap();
+
+    match inner.as_rule() {
+        Rule::identifier => Literal::Variant(inner.as_str().to_string()),
+        Rule::string => Literal::String(strchkconv(inner)),
+        Rule::integer => Literal::Integer(inner.as_str().parse().unwrap()),
+        Rule::float => Literal::Float(inner.as_str().parse().unwrap()),
+        Rule::boolean => Literal::Bool(inner.as_str() == "true"),
+        Rule::list_literal => Literal::List(inner.into_inner().map(literalconv).collect()),
+        _ => unreachable!()
+    }
 }
 
 fn convrsdltype(rsdl_type: Pair<Rule>) -> RSDLType {
@@ -225,7 +376,12 @@ fn convrsdltype(rsdl_type: Pair<Rule>) -> RSDLType {
     match inner.as_rule() {
         Rule::identifier => RSDLType::Identifier(rsdl_type.as_str().to_string()),
         Rule::list_type => convrsdltype_list(inner),
+        Rule::array_type => convrsdltype_array(inner),
+        Rule::tuple_type => convrsdltype_tuple(inner),
         Rule::record_type => convrsdltype_record(inner),
+        Rule::map_type => convrsdltype_map(inner),
+        Rule::set_type => convrsdltype_set(inner),
+        Rule::generic_type => convrsdltype_generic(inner),
         Rule::native_type => convrsdltype_native(inner),
         _ => unreachable!()
     }
@@ -236,9 +392,42 @@ fn convrsdltype_list(list_type: Pair<Rule>) -> RSDLType {
     RSDLType::List(Box::new(eltype))
 }
 
+fn convrsdltype_array(array_type: Pair<Rule>) -> RSDLType {
+    let mut iter = array_type.into_inner();
+    let eltype = convrsdltype(iter.next().unwrap());
+    let len = iter.next().unwrap();
+    assert_eq!(len.as_rule(), Rule::array_len);
+
+    // 超出 usize 范围的长度按 0 处理，由 min_resolv 报告错误
+    RSDLType::Array(Box::new(eltype), len.as_str().parse().unwrap_or(0))
+}
+
+fn convrsdltype_tuple(tuple_type: Pair<Rule>) -> RSDLType {
+    RSDLType::Tuple(tuple_type.into_inner().map(convrsdltype).collect())
+}
+
 fn convrsdltype_record(record_type: Pair<Rule>) -> RSDLType {
     let eltype = convrsdltype(record_type.into_inner().next().unwrap());
-    RSDLType::Record(Box::new(eltype))
+    RSDLType::Map(Box::new(RSDLType::Identifier("str".to_string())), Box::new(eltype))
+}
+
+fn convrsdltype_map(map_type: Pair<Rule>) -> RSDLType {
+    let mut iter = map_type.into_inner();
+    let key_type = convrsdltype(iter.next().unwrap());
+    let value_type = convrsdltype(iter.next().unwrap());
+    RSDLType::Map(Box::new(key_type), Box::new(value_type))
+}
+
+fn convrsdltype_set(set_type: Pair<Rule>) -> RSDLType {
+    let eltype = convrsdltype(set_type.into_inner().next().unwrap());
+    RSDLType::Set(Box::new(eltype))
+}
+
+fn convrsdltype_generic(generic_type: Pair<Rule>) -> RSDLType {
+    let mut iter = generic_type.into_inner();
+    let name = identchkconv(iter.next().unwrap());
+    let args = iter.map(convrsdltype).collect();
+    RSDLType::Generic(name, args)
 }
 
 fn convrsdltype_native(native_type: Pair<Rule>) -> RSDLType {
@@ -276,3 +465,55 @@ fn strchkconv(string: Pair<Rule>) -> String {
         .replace("\\r", "\r")
         .replace("\\\"", "\"")
 }
+
+#[cfg(test)]
+mod tests {
+    use pest::Parser;
+
+    use super::treeconv;
+    use crate::parser::hir::{AttrQuery, TypeDef, TypeDefInner};
+    use crate::parser::pest_parser::{PestRSDLParser, Rule};
+
+    fn parse(src: &str) -> Vec<TypeDef> {
+        let tree = PestRSDLParser::parse(Rule::rsdl_program, src).unwrap();
+        let mut defs = Vec::new();
+        treeconv("test.rsdl", tree, &mut Vec::new(), &mut defs);
+        defs
+    }
+
+    #[test]
+    fn strings_keep_comment_markers_and_spaces() {
+        let defs = parse("[doc = \"-- leading dashes\"]\n[doc(\"  {- not a comment -}\")]\nA(x: int)");
+        assert_eq!(defs[0].attr.string_values("doc").unwrap(), vec!["-- leading dashes", "  {- not a comment -}"]);
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        let defs = parse("-- line comment\n{- block {- nested -} comment -}\nA(x: int) -- trailing");
+        assert_eq!(defs.len(), 1);
+    }
+
+    #[test]
+    fn doc_comments_before_bars_belong_to_next_variant() {
+        let defs = parse("--- 表达式\nExpr : Num(value: int)\n    --- 二元运算\n    | BinOp(op: str)\n    --- 空\n    | Empty");
+        let TypeDefInner::SumType(sum_type) = &defs[0].inner else { panic!() };
+        assert_eq!(defs[0].attr.string_values("doc").unwrap(), vec!["表达式"]);
+        assert!(sum_type.ctors[0].0.is_empty());
+        assert_eq!(sum_type.ctors[1].0.string_values("doc").unwrap(), vec!["二元运算"]);
+        assert_eq!(sum_type.scalar_variants[0].0.string_values("doc").unwrap(), vec!["空"]);
+    }
+
+    #[test]
+    fn stray_doc_comments_are_ordinary_comments() {
+        let defs = parse("A(x: int --- 尾注释\n) --- 文件末尾");
+        let TypeDefInner::SimpleType(ctor) = &defs[0].inner else { panic!() };
+        assert!(ctor.fields[0].attr.is_empty());
+
+        let defs = parse("A(x: int | null --- 可空\n, y: int)\nB : C | D --- 公共字段\nattributes(z: int)");
+        assert_eq!(defs.len(), 2);
+
+        let defs = parse("A(x: int,\n  --- y 的文档\n  y: int)");
+        let TypeDefInner::SimpleType(ctor) = &defs[0].inner else { panic!() };
+        assert_eq!(ctor.fields[1].attr.string_values("doc").unwrap(), vec!["y 的文档"]);
+    }
+}
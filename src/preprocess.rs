@@ -5,7 +5,13 @@ use tracing::warn;
 /// 对单个文件预处理的结果
 #[derive(Debug, Clone)]
 pub struct PreprocessResult {
-    /// 输出代码，`#include` 预处理指令和注释已被移除
+    /// 输出代码，`#include` 预处理指令已被移除
+    ///
+    /// 注释由解析器（见 `rsdl.pest`）处理，因此不会被预处理器移除，
+    /// 字符串中的 `--` 也会被原样保留
+    ///
+    /// 被移除的预处理指令会被替换为空行，因此输出代码的行号与原始文件的行号一一对应，
+    /// 解析输出代码时得到的位置可以直接用于原始文件
     pub output_src: String,
     /// 该文件通过 `#include` 包含的其他文件
     pub includes: Vec<String>
@@ -17,31 +23,41 @@ pub struct PreprocessResult {
 pub fn preprocess(file_name: &str, src: &str) -> PreprocessResult {
     let mut output_src = String::new();
     let mut includes = Vec::new();
+    let mut scan_state = ScanState::default();
 
-    for (idx, line) in src.split("\n").enumerate() {
+    for (idx, line) in src.split('\n').enumerate() {
         let lineno = idx + 1;
         let trimmed = line.trim();
 
-        if trimmed.starts_with("#!") {
+        // 被移除的行也保留一个空行，以保证行号不变。最后一段之后不追加换行，
+        // 以免输出比原始文件多出一行
+        if idx > 0 {
             output_src.push('\n');
-            continue;
         }
-        else if trimmed.starts_with("#include ") {
-            let included_file = trimmed[9..].trim();
+
+        if !scan_state.in_code() {
+            // 块注释或者多行字符串中的行原样交给解析器，其中的 #include 不是预处理指令
+            scan_state.scan_line(line);
+            output_src.push_str(line);
+        }
+        else if trimmed.starts_with("#!") {
+            // shebang 行，直接丢弃
+        }
+        else if let Some(included_file) = trimmed.strip_prefix("#include ") {
+            let included_file = included_file.trim();
             if !included_file.starts_with('"') || !included_file.ends_with('"') {
                 warn!("{file_name}:{lineno}: 无效的 #include: {line}");
-                continue;
+            } else {
+                includes.push(included_file[1..included_file.len() - 1].to_string());
             }
-
-            includes.push(included_file[1..included_file.len() - 1].to_string());
         }
-        else if trimmed.starts_with("#") {
+        else if trimmed.starts_with('#') {
             warn!("{file_name}:{lineno}: 无效的预处理指令: {line}");
         }
-        else if trimmed.starts_with("include ") {
+        else if let Some(included_module) = trimmed.strip_prefix("include ") {
             // be compatible with previous version
-            let included_module = trimmed[8..].trim();
-            let module_path = included_module.split(".");
+            let included_module = included_module.trim();
+            let module_path = included_module.split('.');
 
             #[cfg(windows)] let mut module_path = module_path.collect::<Vec<&str>>().join("\\");
             #[cfg(not(windows))] let mut module_path = module_path.collect::<Vec<&str>>().join("/");
@@ -50,10 +66,8 @@ pub fn preprocess(file_name: &str, src: &str) -> PreprocessResult {
             includes.push(module_path);
         }
         else {
-            let splitted = line.split("--");
-            let mut iter = splitted.into_iter();
-            output_src.push_str(iter.next().unwrap());
-            output_src.push('\n');
+            scan_state.scan_line(line);
+            output_src.push_str(line);
         }
     }
 
@@ -62,3 +76,77 @@ pub fn preprocess(file_name: &str, src: &str) -> PreprocessResult {
         includes
     }
 }
+
+/// 逐行扫描源代码时的状态，用于判断下一行的开头是否位于块注释或者字符串中
+///
+/// 规则与 `rsdl.pest` 一致：块注释可以嵌套，行注释和字符串中的 `{-` 不会开始块注释，
+/// 块注释中的 `"` 也不会开始字符串
+#[derive(Debug, Default)]
+struct ScanState {
+    block_comment_depth: usize,
+    in_string: bool
+}
+
+impl ScanState {
+    fn in_code(&self) -> bool {
+        self.block_comment_depth == 0 && !self.in_string
+    }
+
+    fn scan_line(&mut self, line: &str) {
+        let mut chars = line.chars().peekable();
+        while let Some(ch) = chars.next() {
+            if self.in_string {
+                match ch {
+                    '\\' if chars.peek() == Some(&'"') => { chars.next(); },
+                    '"' => self.in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+
+            match (ch, chars.peek()) {
+                ('{', Some('-')) => {
+                    chars.next();
+                    self.block_comment_depth += 1;
+                },
+                ('-', Some('}')) if self.block_comment_depth > 0 => {
+                    chars.next();
+                    self.block_comment_depth -= 1;
+                },
+                ('-', Some('-')) if self.block_comment_depth == 0 => break,
+                ('"', _) if self.block_comment_depth == 0 => self.in_string = true,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::preprocess;
+
+    #[test]
+    fn output_has_same_lines_as_source() {
+        for src in ["A(x: int)", "A(x: int)\n", "#include \"b.rsdl\"\nA(x: int)\n\n"] {
+            assert_eq!(preprocess("a.rsdl", src).output_src, src.replacen("#include \"b.rsdl\"", "", 1));
+        }
+    }
+
+    #[test]
+    fn includes_are_replaced_by_empty_lines() {
+        let result = preprocess("a.rsdl", "#include \"b.rsdl\"\ninclude c.d\nA(x: int)");
+        assert_eq!(result.includes, vec!["b.rsdl".to_string(), format!("c{}d.asdl", std::path::MAIN_SEPARATOR)]);
+        assert_eq!(result.output_src, "\n\nA(x: int)");
+    }
+
+    #[test]
+    fn includes_in_block_comments_are_ignored() {
+        let src = "{- 已经不再使用\n#include \"old.rsdl\"\n{- 嵌套 -}\ninclude old.module\n-}\n#include \"b.rsdl\"";
+        let result = preprocess("a.rsdl", src);
+        assert_eq!(result.includes, vec!["b.rsdl".to_string()]);
+        assert_eq!(result.output_src, src.replacen("#include \"b.rsdl\"", "", 1));
+
+        let src = "A(x: int) -- {- 行注释中的块注释标记\n[doc = \"{-\"]\n#include \"b.rsdl\"";
+        assert_eq!(preprocess("a.rsdl", src).includes, vec!["b.rsdl".to_string()]);
+    }
+}